@@ -0,0 +1,263 @@
+use std::collections::VecDeque;
+
+use super::*;
+
+/// Names of solvers accepted by `AnySolver::new_rc` - the first one is the default
+pub const SOLVER_NAMES: [&str; 5] = ["speed", "mem", "dir", "bfs01", "sweep"];
+
+/// Solver implementation selected at runtime by name
+pub enum AnySolver {
+    Speed(dijkstra_speed::Solver1),
+    Mem(dijkstra_mem::Solver1),
+    Dir(dijkstra_dir::Solver1),
+    Bfs01(bfs01::Solver1),
+    Sweep(corridor_sweep::Solver1),
+    /// Maze on hex grid - not selected by name, as it interprets the maze differently than other solvers
+    Hex(hex::Solver1),
+}
+
+macro_rules! dispatch {
+    ($self:expr, $solver:ident => $body:expr) => {
+        match $self {
+            AnySolver::Speed($solver) => $body,
+            AnySolver::Mem($solver) => $body,
+            AnySolver::Dir($solver) => $body,
+            AnySolver::Bfs01($solver) => $body,
+            AnySolver::Sweep($solver) => $body,
+            AnySolver::Hex($solver) => $body,
+        }
+    };
+}
+
+impl AnySolver {
+
+    pub fn new_rc(name: &str, r: DimType, c:DimType) -> Result<Self, Error> {
+        let (start, end) = default_endpoints(r, c);
+        Self::new_with_endpoints(name, r, c, start, end)
+    }
+
+    pub fn new_with_endpoints(name: &str, r: DimType, c:DimType, start: NodeAdr, end: NodeAdr) -> Result<Self, Error> {
+        match name {
+            "speed" => dijkstra_speed::Solver1::new_with_endpoints(r, c, start, end).map(AnySolver::Speed),
+            "mem" => dijkstra_mem::Solver1::new_with_endpoints(r, c, start, end).map(AnySolver::Mem),
+            "dir" => dijkstra_dir::Solver1::new_with_endpoints(r, c, start, end).map(AnySolver::Dir),
+            "bfs01" => bfs01::Solver1::new_with_endpoints(r, c, start, end).map(AnySolver::Bfs01),
+            "sweep" => corridor_sweep::Solver1::new_with_endpoints(r, c, start, end).map(AnySolver::Sweep),
+            _ => Err(Error::UnknownSolver(name.to_string())),
+        }
+    }
+
+    /// Makes the node one-way passage, see `dijkstra_speed::Solver1::set_one_way` - supported only by `speed` and `mem` solvers
+    pub fn set_one_way(&mut self, r: DimType, c: DimType, heading: Heading) -> Result<(), Error> {
+        match self {
+            AnySolver::Speed(solver) => solver.set_one_way(r, c, heading),
+            AnySolver::Mem(solver) => solver.set_one_way(r, c, heading),
+            _ => return Err(Error::InvalidArgument("One-way cells are supported only by solvers speed and mem".to_string())),
+        }
+        Ok(())
+    }
+
+    /// Links two nodes with portal, see `dijkstra_speed::Solver1::add_portal` - supported only by `speed` and `mem` solvers
+    pub fn add_portal(&mut self, a: NodeAdr, b: NodeAdr) -> Result<(), Error> {
+        match self {
+            AnySolver::Speed(solver) => solver.add_portal(a, b),
+            AnySolver::Mem(solver) => solver.add_portal(a, b),
+            _ => Err(portals_unsupported()),
+        }
+    }
+
+    /// Sets number of turns which the move thru portal counts as - supported only by `speed` and `mem` solvers
+    pub fn set_portal_cost(&mut self, turns: DimType) -> Result<(), Error> {
+        match self {
+            AnySolver::Speed(solver) => solver.set_portal_cost(turns),
+            AnySolver::Mem(solver) => solver.set_portal_cost(turns),
+            _ => return Err(portals_unsupported()),
+        }
+        Ok(())
+    }
+
+}
+
+fn portals_unsupported() -> Error {
+    Error::InvalidArgument("Portals are supported only by solvers speed and mem".to_string())
+}
+
+impl Solver for AnySolver {
+
+    fn set_passage(&mut self, r: DimType, c: DimType) {
+        dispatch!(self, solver => solver.set_passage(r, c))
+    }
+
+    fn is_valid(&self) -> Result<(), Error> {
+        dispatch!(self, solver => solver.is_valid())
+    }
+
+    fn solve(&self, with_path: bool) -> Option<(DimType, VecDeque<NodeAdr>)> {
+        dispatch!(self, solver => solver.solve(with_path))
+    }
+
+    fn solve_multi(&self, starts: &[StartPoint], ends: &[NodeAdr], with_path: bool) -> Option<MultiSolution> {
+        dispatch!(self, solver => solver.solve_multi(starts, ends, with_path))
+    }
+
+    fn solve_and_drop(self, with_path: bool) -> Option<(DimType, VecDeque<NodeAdr>)> {
+        dispatch!(self, solver => solver.solve_and_drop(with_path))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_rc() {
+        for name in SOLVER_NAMES.iter() {
+            assert!(AnySolver::new_rc(name, 4, 5).is_ok());
+        }
+        assert!(AnySolver::new_rc("foo", 4, 5).is_err());
+        assert!(AnySolver::new_rc("speed", 1, 5).is_err());
+    }
+
+    #[test]
+    fn solve_straight() {
+        for name in SOLVER_NAMES.iter() {
+            let mut solv = AnySolver::new_rc(name, 3, 4).unwrap();
+            for c in 0..4 {
+                solv.set_passage(1, c);
+            }
+            assert!(solv.is_valid().is_ok());
+            assert_eq!(solv.solve_and_drop(true), Some((0, (0..4).map(|c| (1, c)).collect())), "solver {}", name);
+        }
+    }
+
+    #[test]
+    fn endpoints() {
+        for name in SOLVER_NAMES.iter() {
+            assert!(matches!(AnySolver::new_with_endpoints(name, 4, 5, (4, 0), (0, 0)), Err(Error::EndpointOutOfRange((4, 0)))));
+            assert!(matches!(AnySolver::new_with_endpoints(name, 4, 5, (0, 0), (0, 5)), Err(Error::EndpointOutOfRange((0, 5)))));
+            // Way from bottom-left to top-right corner thru the last column
+            let mut solv = AnySolver::new_with_endpoints(name, 4, 5, (3, 0), (0, 4)).unwrap();
+            assert!(matches!(solv.is_valid(), Err(Error::StartBlocked((3, 0)))));
+            for c in 0..5 {
+                solv.set_passage(3, c);
+            }
+            assert!(matches!(solv.is_valid(), Err(Error::EndBlocked((0, 4)))));
+            for r in 0..3 {
+                solv.set_passage(r, 4);
+            }
+            assert!(solv.is_valid().is_ok());
+            assert_eq!(
+                solv.solve(true),
+                Some((1, vec![(3, 0), (3, 1), (3, 2), (3, 3), (3, 4), (2, 4), (1, 4), (0, 4)].into_iter().collect())),
+                "solver {}", name
+            );
+        }
+    }
+
+    #[test]
+    fn solve_multi() {
+        for name in SOLVER_NAMES.iter() {
+            // Two horizontal corridors joined at the right edge
+            let mut solv = AnySolver::new_rc(name, 6, 6).unwrap();
+            for c in 0..6 {
+                solv.set_passage(1, c);
+                solv.set_passage(4, c);
+            }
+            solv.set_passage(2, 5);
+            solv.set_passage(3, 5);
+            let ends = [(0, 0), (4, 4)];
+            // Start (4,0) heading up needs one turn to go right - still better than two turns from (1,0);
+            // its second occurrence (without heading) is ignored
+            let starts = [((9, 9), None), ((1, 0), None), ((4, 0), Some(Heading::Up)), ((4, 0), None)];
+            assert_eq!(
+                solv.solve_multi(&starts, &ends, true),
+                Some(MultiSolution { rank:1, start:2, end:1, path:(0..5).map(|c| (4, c)).collect() }),
+                "solver {}", name
+            );
+            assert_eq!(
+                solv.solve_multi(&[((1, 0), None), ((4, 0), Some(Heading::Right))], &ends, false),
+                Some(MultiSolution { rank:0, start:1, end:1, path:VecDeque::new() }),
+                "solver {}", name
+            );
+            assert_eq!(solv.solve_multi(&[((1, 0), None)], &[(4, 5)], false).map(|s| s.rank), Some(1), "solver {}", name);
+            assert_eq!(solv.solve_multi(&starts, &[(0, 0), (5, 5)], false), None, "solver {}", name);
+        }
+    }
+
+    #[test]
+    fn start_heading() {
+        for name in SOLVER_NAMES.iter() {
+            // Horizontal corridor with dead end branch going down from its middle
+            let mut solv = AnySolver::new_rc(name, 5, 6).unwrap();
+            for c in 0..6 {
+                solv.set_passage(1, c);
+            }
+            solv.set_passage(2, 2);
+            solv.set_passage(3, 2);
+            let solve = |heading, end| solv.solve_multi(&[((1, 2), Some(heading))], &[end], true).map(|s| (s.rank, s.path));
+            // Moving back from start is a turn, the same as moving sideways
+            assert_eq!(solve(Heading::Right, (1, 0)), Some((1, vec![(1, 2), (1, 1), (1, 0)].into_iter().collect())), "solver {}", name);
+            assert_eq!(solve(Heading::Left, (1, 0)).map(|s| s.0), Some(0), "solver {}", name);
+            assert_eq!(solve(Heading::Right, (1, 5)).map(|s| s.0), Some(0), "solver {}", name);
+            assert_eq!(solve(Heading::Left, (1, 5)).map(|s| s.0), Some(1), "solver {}", name);
+            assert_eq!(solve(Heading::Up, (3, 2)).map(|s| s.0), Some(1), "solver {}", name);
+            assert_eq!(solve(Heading::Right, (3, 2)).map(|s| s.0), Some(1), "solver {}", name);
+            assert_eq!(solve(Heading::Down, (3, 2)).map(|s| s.0), Some(0), "solver {}", name);
+        }
+    }
+
+    #[test]
+    fn portals() {
+        for name in ["speed", "mem"].iter() {
+            // Portal (1,2) - (3,3) is a shortcut of the way with 2 turns
+            let mut solv = AnySolver::new_with_endpoints(name, 5, 6, (1, 0), (3, 5)).unwrap();
+            for node in [(1, 0), (1, 1), (1, 2), (2, 2), (3, 2), (3, 3), (3, 4), (3, 5)].iter() {
+                solv.set_passage(node.0, node.1);
+            }
+            assert_eq!(solv.solve(false).map(|s| s.0), Some(2), "solver {}", name);
+            solv.add_portal((1, 2), (3, 3)).unwrap();
+            assert_eq!(
+                solv.solve(true),
+                Some((0, vec![(1, 0), (1, 1), (1, 2), (3, 3), (3, 4), (3, 5)].into_iter().collect())),
+                "solver {}", name
+            );
+            solv.set_portal_cost(1).unwrap();
+            assert_eq!(solv.solve(false).map(|s| s.0), Some(1), "solver {}", name);
+            solv.set_portal_cost(5).unwrap();
+            assert_eq!(solv.solve(false).map(|s| s.0), Some(2), "solver {}", name);
+            assert!(matches!(solv.add_portal((1, 2), (5, 3)), Err(Error::EndpointOutOfRange((5, 3)))));
+            solv.set_portal_cost(1).unwrap();
+            assert_eq!(solv.solve_and_drop(false).map(|s| s.0), Some(1), "solver {}", name);
+        }
+        let mut solv = AnySolver::new_rc("dir", 5, 6).unwrap();
+        assert!(matches!(solv.add_portal((1, 2), (3, 3)), Err(Error::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn one_way() {
+        for name in ["speed", "mem"].iter() {
+            // Loop of two corridors, the upper one may be left only towards the end
+            let mut solv = AnySolver::new_with_endpoints(name, 5, 5, (1, 0), (3, 0)).unwrap();
+            for c in 0..5 {
+                solv.set_passage(1, c);
+                solv.set_passage(3, c);
+            }
+            solv.set_passage(2, 4);
+            assert_eq!(solv.solve(false).map(|s| s.0), Some(2), "solver {}", name);
+            solv.set_one_way(2, 4, Heading::Up).unwrap();
+            assert_eq!(solv.solve(false), None, "solver {}", name);
+            solv.set_one_way(2, 4, Heading::Down).unwrap();
+            solv.set_one_way(1, 0, Heading::Right).unwrap();
+            assert_eq!(solv.solve(false).map(|s| s.0), Some(2), "solver {}", name);
+            // Start heading up is not possible to leave
+            solv.set_one_way(1, 0, Heading::Up).unwrap();
+            assert_eq!(solv.solve_and_drop(false), None, "solver {}", name);
+        }
+        let mut solv = AnySolver::new_rc("bfs01", 5, 6).unwrap();
+        assert!(matches!(solv.set_one_way(1, 0, Heading::Up), Err(Error::InvalidArgument(_))));
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::{HashMap, VecDeque};
+
+use super::*;
+
+/// To store number of turns
+type RankType = DimType;
+
+/// Value of `prev` for states which were not reached yet
+const PREV_NONE: u8 = 0xFF;
+/// Value of `prev` for start node states
+const PREV_START: u8 = 0xFE;
+
+pub struct Solver1 {
+    data: Vec<u8>,
+    r_dim: DimType,
+    c_dim: DimType,
+    start: NodeAdr,
+    end: NodeAdr,
+}
+
+impl Solver1 {
+
+    pub fn new_rc(r: DimType, c:DimType) -> Result<Self, Error> {
+        let (start, end) = default_endpoints(r, c);
+        Self::new_with_endpoints(r, c, start, end)
+    }
+
+    /// Creates solver for maze with given start and end points
+    pub fn new_with_endpoints(r: DimType, c:DimType, start: NodeAdr, end: NodeAdr) -> Result<Self, Error> {
+        if r < 2 || c < 2 { return Err(Error::TooSmallDimensions { rows:r, cols:c }); }
+        // Rough estimation of maximal number of turns
+        // If this is too taught, set RankType to usize
+        if (r as usize + c as usize) / 2 > RankType::MAX as usize { return Err(Error::TooBigDimensions { rows:r, cols:c }); }
+        check_endpoints(r, c, start, end)?;
+        Ok(Solver1 {
+            data: vec![0; r as usize * c as usize],
+            r_dim:r,
+            c_dim:c,
+            start,
+            end,
+        })
+    }
+
+    #[inline]
+    fn idx(&self, r: DimType, c:DimType) -> usize {
+        r as usize * self.c_dim as usize + c as usize
+    }
+
+    #[inline]
+    fn rc(&self, idx: usize) -> NodeAdr {
+        let r = idx / self.c_dim as usize;
+        let c = idx - r * self.c_dim as usize;
+        (r as DimType, c as DimType)
+    }
+
+    #[inline]
+    fn is_passage(&self, r: DimType, c:DimType) -> bool {
+        self.data[self.idx(r, c)] == 1
+    }
+
+    /// Returns index of neighbor node in given direction if it is a passage
+    #[inline]
+    fn neighbor(&self, idx: usize, heading: Heading) -> Option<usize> {
+        let (r, c) = self.rc(idx);
+        let c_dim = self.c_dim as usize;
+        let n_idx = match heading {
+            Heading::Up if r > 0 => idx - c_dim,
+            Heading::Right if c < self.c_dim - 1 => idx + 1,
+            Heading::Down if r < self.r_dim - 1 => idx + c_dim,
+            Heading::Left if c > 0 => idx - 1,
+            _ => return None,
+        };
+        if self.data[n_idx] == 1 { Some(n_idx) } else { None }
+    }
+
+}
+
+/// Search state is the node index and the axis (0 - vertical, 1 - horizontal) of the move by which
+/// it was entered: `idx * 2 + axis`. Moving back along the same line is never a part of the simplest way,
+/// so both headings on the same axis may share one state - this halves the memory in compare to `dijkstra_dir`.
+#[inline]
+fn axis(heading: Heading) -> usize {
+    match heading {
+        Heading::Up | Heading::Down => 0,
+        Heading::Right | Heading::Left => 1,
+    }
+}
+
+#[inline]
+fn heading_code(heading: Heading) -> u8 {
+    match heading {
+        Heading::Up => 0,
+        Heading::Right => 1,
+        Heading::Down => 2,
+        Heading::Left => 3,
+    }
+}
+
+impl Solver for Solver1 {
+
+    fn set_passage(&mut self, r: DimType, c: DimType) {
+        let idx = self.idx(r, c);
+        self.data[idx] = 1;
+    }
+
+    fn is_valid(&self) -> Result<(), Error> {
+        if !self.is_passage(self.start.0, self.start.1) { return Err(Error::StartBlocked(self.start)); }
+        if !self.is_passage(self.end.0, self.end.1) { return Err(Error::EndBlocked(self.end)); }
+        Ok(())
+    }
+
+    fn solve(&self, with_path: bool) -> Option<(DimType, VecDeque<NodeAdr>)> {
+        if self.is_valid().is_err() { return None; }
+        self.solve_multi(&[(self.start, None)], &[self.end], with_path)
+            .map(|solution| (solution.rank, solution.path))
+    }
+
+    /// 0-1 BFS: moves without turn (weight 0) are pushed to the front of the deque,
+    /// and moves with turn (weight 1) to its back. This way deque is always sorted by rank
+    /// and there is no need to search for minimal one - total complexity is O(X*Y).
+    fn solve_multi(&self, starts: &[StartPoint], ends: &[NodeAdr], with_path: bool) -> Option<MultiSolution> {
+        let is_valid_node = |node: &NodeAdr| node.0 < self.r_dim && node.1 < self.c_dim && self.is_passage(node.0, node.1);
+        let mut rank = vec![RankType::MAX; self.data.len() * 2];
+        // Heading code of the move into the node (bits 0-1) and axis of previous state (bit 2)
+        let mut prev = vec![PREV_NONE; self.data.len() * 2];
+        // Bit set of end nodes
+        let mut is_end = vec![0u64; self.data.len().div_ceil(64)];
+        for node in ends.iter().filter(|node| is_valid_node(node)) {
+            let idx = self.idx(node.0, node.1);
+            is_end[idx / 64] |= 1 << (idx % 64);
+        }
+        let mut todo = VecDeque::new();
+        // Start node => its heading, to charge the move back from it
+        let mut start_heading = HashMap::new();
+        for (node, heading) in starts.iter().filter(|(node, _)| is_valid_node(node)) {
+            let idx = self.idx(node.0, node.1);
+            if prev[idx * 2] == PREV_START || prev[idx * 2 + 1] == PREV_START { continue; }
+            if let Some(heading) = heading {
+                start_heading.insert(idx, *heading);
+            }
+            // Without heading, first move from start node in any direction is not a turn
+            let axes = match heading {
+                Some(heading) => vec![axis(*heading)],
+                None => vec![0, 1],
+            };
+            for axis in axes {
+                let state = idx * 2 + axis;
+                rank[state] = 0;
+                prev[state] = PREV_START;
+                todo.push_back(state);
+            }
+        }
+
+        let mut end_state = None;
+        while let Some(state) = todo.pop_front() {
+            let (idx, cur_axis) = (state / 2, state % 2);
+            if is_end[idx / 64] & (1 << (idx % 64)) != 0 {
+                end_state = Some(state);
+                break;
+            }
+            let cur_rank = rank[state];
+            for heading in Heading::ALL.iter() {
+                if let Some(n_idx) = self.neighbor(idx, *heading) {
+                    let n_axis = axis(*heading);
+                    let n_state = n_idx * 2 + n_axis;
+                    // Moving back keeps the axis, but at start point with heading it is a turn as well
+                    let turn = n_axis != cur_axis
+                        || (prev[state] == PREV_START && start_heading.get(&idx) == Some(&heading.opposite()));
+                    let n_rank = if turn { cur_rank + 1 } else { cur_rank };
+                    if n_rank < rank[n_state] {
+                        rank[n_state] = n_rank;
+                        prev[n_state] = heading_code(*heading) | (cur_axis as u8) << 2;
+                        if turn { todo.push_back(n_state); } else { todo.push_front(n_state); }
+                    }
+                }
+            }
+        }
+
+        let end_state = end_state?;
+        let mut path = VecDeque::new();
+        // Path is followed also without with_path, to find which start it begins with
+        let c_dim = self.c_dim as usize;
+        let mut state = end_state;
+        let mut path_len = 0;
+        loop {
+            let idx = state / 2;
+            if with_path {
+                path.push_front(self.rc(idx));
+            }
+            let code = prev[state];
+            if code == PREV_START { break; }
+            assert_ne!(code, PREV_NONE, "Logical error - every state on path should be reached");
+            let prev_idx = match code & 3 {
+                0 => idx + c_dim,
+                1 => idx - 1,
+                2 => idx - c_dim,
+                _ => idx + 1,
+            };
+            state = prev_idx * 2 + (code >> 2) as usize;
+            path_len += 1;
+            if path_len > self.data.len() {
+                panic!("Something wrong - Cycle detected");
+            }
+        }
+        let start_node = self.rc(state / 2);
+        let end_node = self.rc(end_state / 2);
+        Some(MultiSolution {
+            rank: rank[end_state],
+            start: starts.iter().position(|(node, _)| *node == start_node).expect("Logical error - path should begin at start"),
+            end: ends.iter().position(|node| *node == end_node).expect("Logical error - path should finish at end"),
+            path,
+        })
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use ndarray::array;
+    use ndarray::Array;
+    use ndarray_rand::RandomExt;
+    use ndarray_rand::rand_distr::Uniform;
+    use super::*;
+    use crate::tests::{init_from_rand_array, count_turns};
+
+    #[test]
+    fn neighbor() {
+        let arr = array![
+            [0,0,1,0,0u8],
+            [1,1,1,1,0u8],
+            [0,1,1,0,0u8],
+            [0,0,0,1,1u8],
+        ];
+        let adim = arr.shape();
+        let mut solv = Solver1::new_rc(adim[0] as DimType, adim[1] as DimType).unwrap();
+        for (c, v) in arr.indexed_iter() {
+            if *v == 1 {
+                solv.set_passage(c.0 as DimType, c.1 as DimType);
+            }
+        }
+        let neighbors = |r, c| Heading::ALL.iter()
+            .filter_map(|h| solv.neighbor(solv.idx(r, c), *h))
+            .map(|idx| solv.rc(idx))
+            .collect::<Vec<_>>();
+        assert_eq!(neighbors(1, 1), vec![(1, 2), (2, 1), (1, 0)]);
+        assert_eq!(neighbors(1, 2), vec![(0, 2), (1, 3), (2, 2), (1, 1)]);
+        assert_eq!(neighbors(0, 2), vec![(1, 2)]);
+        assert_eq!(neighbors(3, 4), vec![(3, 3)]);
+        assert_eq!(neighbors(3, 0), vec![]);
+    }
+
+    #[test]
+    fn solve_book() {
+        let arr = array![
+            [0,0,0,0,0,0,0,0,0u8],
+            [1,1,1,1,0,1,1,1,0u8],
+            [0,1,0,1,0,1,0,1,0u8],
+            [0,1,0,1,1,1,0,1,0u8],
+            [0,1,0,0,0,0,0,1,0u8],
+            [0,1,1,1,1,0,0,1,0u8],
+            [0,1,0,0,1,1,1,1,1u8],
+            [0,0,0,0,0,0,0,0,0u8],
+        ];
+        let adim = arr.shape();
+        let mut solv = Solver1::new_rc(adim[0] as DimType, adim[1] as DimType).unwrap();
+        for (c, v) in arr.indexed_iter() {
+            if *v == 1 {
+                solv.set_passage(c.0 as DimType, c.1 as DimType);
+            }
+        }
+        assert!(solv.is_valid().is_ok());
+        assert_eq!(
+            format!("{:?}", solv.solve(true).unwrap()),
+            format!("{:?}", (4, vec![(1,0),(1,1),(2,1),(3,1),(4,1),(5,1),(5,2),(5,3),(5,4),(6,4),(6,5),(6,6),(6,7),(6,8)]))
+        );
+    }
+
+    #[test]
+    fn solve_no_solution() {
+        let arr = array![
+            [0,0,0,0,0,0,0,0,0u8],
+            [1,1,1,1,0,1,1,1,0u8],
+            [0,1,0,1,0,1,0,1,0u8],
+            [0,1,0,1,1,1,0,1,0u8],
+            [0,1,0,1,0,0,0,0,0u8],
+            [0,1,1,1,0,0,0,1,0u8],
+            [0,1,0,0,1,1,1,1,1u8],
+            [0,0,0,0,0,0,0,0,0u8],
+        ];
+        let adim = arr.shape();
+        let mut solv = Solver1::new_rc(adim[0] as DimType, adim[1] as DimType).unwrap();
+        for (c, v) in arr.indexed_iter() {
+            if *v == 1 {
+                solv.set_passage(c.0 as DimType, c.1 as DimType);
+            }
+        }
+        assert!(solv.is_valid().is_ok());
+        assert_eq!(solv.solve(false), None );
+    }
+
+    #[test]
+    fn cross_check() {
+        let mut rng = rand::thread_rng();
+        let r_dim = rng.gen_range(5, 40);
+        let c_dim = rng.gen_range(5, 40);
+        let arr = Array::random((r_dim, c_dim), Uniform::new_inclusive(0, 100));
+        let mut solv = Solver1::new_rc(r_dim as DimType, c_dim as DimType).unwrap();
+        let mut solv_dir = dijkstra_dir::Solver1::new_rc(r_dim as DimType, c_dim as DimType).unwrap();
+        init_from_rand_array(&mut solv, &arr.view(), 75);
+        init_from_rand_array(&mut solv_dir, &arr.view(), 75);
+        let solution = solv.solve(true);
+        assert_eq!(solution.as_ref().map(|s| s.0), solv_dir.solve(false).map(|s| s.0));
+        if let Some((rank, path)) = solution {
+            assert_eq!(count_turns(&path), rank);
+            assert_eq!(path.front(), Some(&solv.start));
+            assert_eq!(path.back(), Some(&solv.end));
+        }
+    }
+}
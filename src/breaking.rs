@@ -0,0 +1,192 @@
+//! Finding the simplest way when up to K walls may be broken thru.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+use super::*;
+use grid::Grid;
+
+/// Maximal number of search states (12 bytes each) of `Solver1::solve`
+pub const MAX_STATES: usize = 1 << 28;
+
+/// Value of `prev` for states which were not reached yet
+const PREV_NONE: u32 = u32::MAX;
+
+/// The simplest way found by `Solver1::solve`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BreakingSolution {
+    /// Number of turns
+    pub rank: DimType,
+    /// Walls broken thru, in order along the way
+    pub broken: Vec<NodeAdr>,
+    /// Nodes of the way - empty if not requested
+    pub path: VecDeque<NodeAdr>,
+}
+
+pub struct Solver1 {
+    grid: Grid,
+}
+
+impl Solver1 {
+
+    pub fn new_rc(r: DimType, c:DimType) -> Result<Self, Error> {
+        let (start, end) = default_endpoints(r, c);
+        Self::new_with_endpoints(r, c, start, end)
+    }
+
+    /// Creates solver for maze with given start and end points
+    pub fn new_with_endpoints(r: DimType, c:DimType, start: NodeAdr, end: NodeAdr) -> Result<Self, Error> {
+        Ok(Solver1 { grid: Grid::new_with_endpoints(r, c, start, end)? })
+    }
+
+    pub fn set_passage(&mut self, r: DimType, c: DimType) {
+        self.grid.set_cell(r, c, 1);
+    }
+
+    /// Checks that both start and end points are passages
+    pub fn is_valid(&self) -> Result<(), Error> {
+        self.grid.is_valid()
+    }
+
+    /// Finds the way with the fewest number of turns, which may go thru at most `k` walls,
+    /// and among such ways the one breaking the fewest walls.
+    ///
+    /// Search runs on (node, axis, number of walls broken so far) states, like `lex::solve_shortest`,
+    /// with ranks being pairs (turns, broken walls) compared lexicographically and packed in one u64.
+    /// `k` greater than the number of walls in the maze works the same as that number.
+    /// Returns error if the number of states (cells * 2 * (k + 1)) exceeds `MAX_STATES`.
+    pub fn solve(&self, k: usize, with_path: bool) -> Result<Option<BreakingSolution>, Error> {
+        let grid = &self.grid;
+        if grid.is_valid().is_err() { return Ok(None); }
+        let k = k.min(grid.data.iter().filter(|v| **v == 0).count());
+        // State = (node index * 2 + axis (0 - vertical, 1 - horizontal)) * layers + number of broken walls
+        let layers = k.checked_add(1);
+        let len = layers.and_then(|layers| grid.data.len().checked_mul(2)?.checked_mul(layers));
+        let (layers, len) = match (layers, len) {
+            (Some(layers), Some(len)) if len <= MAX_STATES => (layers, len),
+            _ => return Err(Error::InvalidArgument(format!("Too many walls to break thru: {}", k))),
+        };
+        // Rank = turns << 32 | broken walls
+        let mut rank = vec![u64::MAX; len];
+        let mut prev = vec![PREV_NONE; len];
+        let mut todo = BinaryHeap::new();
+        // First move from start node in any direction is not a turn
+        for axis in 0..2 {
+            let state = (grid.idx(grid.start) * 2 + axis) * layers;
+            rank[state] = 0;
+            prev[state] = state as u32;
+            todo.push(Reverse((0, state)));
+        }
+        let mut end_state = None;
+        while let Some(Reverse((state_rank, state))) = todo.pop() {
+            if rank[state] < state_rank { continue; }
+            let (turns, broken) = (state_rank >> 32, (state_rank & u32::MAX as u64) as usize);
+            let idx = state / layers / 2;
+            if grid.rc(idx) == grid.end {
+                end_state = Some(state);
+                break;
+            }
+            for heading in Heading::ALL.iter() {
+                let n_idx = match grid.step(idx, *heading) {
+                    Some(n_idx) => n_idx,
+                    None => continue,
+                };
+                let next_broken = if grid.data[n_idx] != 0 { broken } else { broken + 1 };
+                if next_broken > k { continue; }
+                let axis = *heading as usize % 2;
+                let next = (n_idx * 2 + axis) * layers + next_broken;
+                let next_turns = if (state / layers) % 2 == axis { turns } else { turns + 1 };
+                let next_rank = next_turns << 32 | next_broken as u64;
+                if next_rank < rank[next] {
+                    rank[next] = next_rank;
+                    prev[next] = state as u32;
+                    todo.push(Reverse((next_rank, next)));
+                }
+            }
+        }
+
+        let end_state = match end_state {
+            Some(end_state) => end_state,
+            None => return Ok(None),
+        };
+        let mut broken = Vec::new();
+        let mut path = VecDeque::new();
+        let mut state = end_state;
+        loop {
+            let node = grid.rc(state / layers / 2);
+            if !grid.is_passage(node) { broken.push(node); }
+            if with_path { path.push_front(node); }
+            if prev[state] as usize == state { break; }
+            state = prev[state] as usize;
+        }
+        broken.reverse();
+        Ok(Some(BreakingSolution { rank: (rank[end_state] >> 32) as DimType, broken, path }))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{rand_maze, reference_rank, count_turns};
+
+    #[test]
+    fn break_walls() {
+        // Straight corridor blocked by two walls, with the way around them
+        let mut solv = Solver1::new_rc(5, 7).unwrap();
+        for c in [0, 1, 2, 4, 6].iter() {
+            solv.set_passage(1, *c);
+        }
+        for c in 2..7 {
+            solv.set_passage(3, c);
+        }
+        solv.set_passage(2, 2);
+        solv.set_passage(2, 6);
+        assert_eq!(solv.solve(0, false).unwrap().map(|s| (s.rank, s.broken)), Some((2, vec![])));
+        assert_eq!(solv.solve(1, false).unwrap().map(|s| (s.rank, s.broken)), Some((2, vec![])));
+        assert_eq!(
+            solv.solve(2, true).unwrap(),
+            Some(BreakingSolution { rank:1, broken:vec![(1, 3), (1, 5)], path:(0..7).map(|c| (1, c)).chain(vec![(2, 6), (3, 6)]).collect() })
+        );
+        assert_eq!(solv.solve(5, false).unwrap().map(|s| s.rank), Some(1));
+
+        // Not reachable without breaking
+        let mut solv = Solver1::new_rc(4, 4).unwrap();
+        solv.set_passage(1, 0);
+        solv.set_passage(2, 3);
+        assert!(solv.solve(2, false).unwrap().is_none());
+        assert_eq!(solv.solve(3, false).unwrap().map(|s| (s.rank, s.broken.len())), Some((1, 3)));
+        // Number of walls to break is limited by the number of walls
+        assert_eq!(solv.solve(usize::MAX, false).unwrap().map(|s| (s.rank, s.broken.len())), Some((1, 3)));
+
+        // Too many states to keep in memory
+        let mut solv = Solver1::new_rc(2000, 2000).unwrap();
+        solv.set_passage(1, 0);
+        solv.set_passage(1998, 1999);
+        assert!(matches!(solv.solve(MAX_STATES / (2000 * 2000 * 2), false), Err(Error::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn cross_check() {
+        let maze = rand_maze(30, 60);
+        let mut solv = Solver1::new_rc(maze.rows(), maze.cols()).unwrap();
+        for (r, c) in maze.passages() {
+            solv.set_passage(r, c);
+        }
+        assert_eq!(solv.solve(0, false).unwrap().map(|s| s.rank), reference_rank(&maze));
+        let mut last_rank = DimType::MAX;
+        for k in 0..4 {
+            if let Some(BreakingSolution { rank, broken, path }) = solv.solve(k, true).unwrap() {
+                // More walls allowed to break never make the way worse
+                assert!(rank <= last_rank);
+                last_rank = rank;
+                assert!(broken.len() <= k);
+                assert_eq!(count_turns(&path), rank);
+                assert_eq!(path.iter().filter(|node| !maze.is_passage(node.0, node.1)).count(), broken.len());
+            }
+        }
+    }
+}
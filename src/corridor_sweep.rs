@@ -0,0 +1,320 @@
+use std::collections::VecDeque;
+
+use super::*;
+
+/// To store number of turns
+type RankType = DimType;
+
+/// Bit flags in `swept` - axis along which node was already swept
+const SWEPT_V: u8 = 1;
+const SWEPT_H: u8 = 2;
+
+pub struct Solver1 {
+    data: Vec<u8>,
+    r_dim: DimType,
+    c_dim: DimType,
+    start: NodeAdr,
+    end: NodeAdr,
+}
+
+impl Solver1 {
+
+    pub fn new_rc(r: DimType, c:DimType) -> Result<Self, Error> {
+        let (start, end) = default_endpoints(r, c);
+        Self::new_with_endpoints(r, c, start, end)
+    }
+
+    /// Creates solver for maze with given start and end points
+    pub fn new_with_endpoints(r: DimType, c:DimType, start: NodeAdr, end: NodeAdr) -> Result<Self, Error> {
+        if r < 2 || c < 2 { return Err(Error::TooSmallDimensions { rows:r, cols:c }); }
+        // Rough estimation of maximal number of turns
+        // If this is too taught, set RankType to usize
+        if (r as usize + c as usize) / 2 > RankType::MAX as usize { return Err(Error::TooBigDimensions { rows:r, cols:c }); }
+        // Node indexes are stored as u32 in `solve()`
+        if r as usize * c as usize > u32::MAX as usize { return Err(Error::TooBigDimensions { rows:r, cols:c }); }
+        check_endpoints(r, c, start, end)?;
+        Ok(Solver1 {
+            data: vec![0; r as usize * c as usize],
+            r_dim:r,
+            c_dim:c,
+            start,
+            end,
+        })
+    }
+
+    #[inline]
+    fn idx(&self, r: DimType, c:DimType) -> usize {
+        r as usize * self.c_dim as usize + c as usize
+    }
+
+    #[inline]
+    fn rc(&self, idx: usize) -> NodeAdr {
+        let r = idx / self.c_dim as usize;
+        let c = idx - r * self.c_dim as usize;
+        (r as DimType, c as DimType)
+    }
+
+    #[inline]
+    fn is_passage(&self, r: DimType, c:DimType) -> bool {
+        self.data[self.idx(r, c)] == 1
+    }
+
+    /// Returns index of neighbor node in given direction if it is a passage
+    #[inline]
+    fn neighbor(&self, idx: usize, heading: Heading) -> Option<usize> {
+        let (r, c) = self.rc(idx);
+        let c_dim = self.c_dim as usize;
+        let n_idx = match heading {
+            Heading::Up if r > 0 => idx - c_dim,
+            Heading::Right if c < self.c_dim - 1 => idx + 1,
+            Heading::Down if r < self.r_dim - 1 => idx + c_dim,
+            Heading::Left if c > 0 => idx - 1,
+            _ => return None,
+        };
+        if self.data[n_idx] == 1 { Some(n_idx) } else { None }
+    }
+
+}
+
+/// Working data of single `solve()` run
+struct Sweep {
+    rank: Vec<RankType>,
+    /// Node at which the straight segment leading to given node begins
+    prev: Vec<u32>,
+    swept: Vec<u8>,
+}
+
+impl Sweep {
+    /// Walks from `from` node in both directions along given axis until wall, or until node which was already
+    /// swept along the same axis - all nodes behind it were already reached with no bigger rank.
+    /// Nodes reached for the first time get `rank` and are appended to `next`.
+    fn sweep(&mut self, solver: &Solver1, from: usize, axis: u8, rank: RankType, next: &mut Vec<usize>) {
+        self.swept[from] |= axis;
+        let headings = if axis == SWEPT_V { [Heading::Up, Heading::Down] } else { [Heading::Right, Heading::Left] };
+        for heading in headings.iter() {
+            self.sweep_ahead(solver, from, *heading, rank, next);
+        }
+    }
+
+    /// Walks from `from` node in given direction only, like `sweep`, but without marking `from` node as swept
+    /// (used for start point with heading - the move back from it is a turn).
+    fn sweep_ahead(&mut self, solver: &Solver1, from: usize, heading: Heading, rank: RankType, next: &mut Vec<usize>) {
+        let axis = match heading {
+            Heading::Up | Heading::Down => SWEPT_V,
+            Heading::Right | Heading::Left => SWEPT_H,
+        };
+        let mut idx = from;
+        while let Some(n_idx) = solver.neighbor(idx, heading) {
+            if self.swept[n_idx] & axis != 0 { break; }
+            self.swept[n_idx] |= axis;
+            if self.rank[n_idx] == RankType::MAX {
+                self.rank[n_idx] = rank;
+                self.prev[n_idx] = from as u32;
+                next.push(n_idx);
+            }
+            idx = n_idx;
+        }
+    }
+}
+
+impl Solver for Solver1 {
+
+    fn set_passage(&mut self, r: DimType, c: DimType) {
+        let idx = self.idx(r, c);
+        self.data[idx] = 1;
+    }
+
+    fn is_valid(&self) -> Result<(), Error> {
+        if !self.is_passage(self.start.0, self.start.1) { return Err(Error::StartBlocked(self.start)); }
+        if !self.is_passage(self.end.0, self.end.1) { return Err(Error::EndBlocked(self.end)); }
+        Ok(())
+    }
+
+    fn solve(&self, with_path: bool) -> Option<(DimType, VecDeque<NodeAdr>)> {
+        if self.is_valid().is_err() { return None; }
+        self.solve_multi(&[(self.start, None)], &[self.end], with_path)
+            .map(|solution| (solution.rank, solution.path))
+    }
+
+    /// BFS over straight segments: all nodes reachable from start without a turn get rank 0,
+    /// then all nodes reachable from them with one turn get rank 1 and so on.
+    /// Each node is swept at most once along each axis, so the complexity is O(X*Y).
+    fn solve_multi(&self, starts: &[StartPoint], ends: &[NodeAdr], with_path: bool) -> Option<MultiSolution> {
+        let is_valid_node = |node: &NodeAdr| node.0 < self.r_dim && node.1 < self.c_dim && self.is_passage(node.0, node.1);
+        let mut sweep = Sweep {
+            rank: vec![RankType::MAX; self.data.len()],
+            prev: vec![0; self.data.len()],
+            swept: vec![0; self.data.len()],
+        };
+        let mut frontier = Vec::new();
+        for (node, heading) in starts.iter().filter(|(node, _)| is_valid_node(node)) {
+            let start = self.idx(node.0, node.1);
+            if sweep.rank[start] == 0 && sweep.prev[start] == start as u32 { continue; }
+            sweep.rank[start] = 0;
+            sweep.prev[start] = start as u32;
+            frontier.push(start);
+            // Without heading, first move from start node in any direction is not a turn.
+            // With heading, start node is swept along its axis in the next round, so that the move back is a turn.
+            match heading {
+                Some(heading) => sweep.sweep_ahead(self, start, *heading, 0, &mut frontier),
+                None => {
+                    sweep.sweep(self, start, SWEPT_V, 0, &mut frontier);
+                    sweep.sweep(self, start, SWEPT_H, 0, &mut frontier);
+                }
+            }
+        }
+        let ends: Vec<(usize, usize)> = ends.iter().enumerate()
+            .filter(|(_, node)| is_valid_node(node))
+            .map(|(i, node)| (i, self.idx(node.0, node.1)))
+            .collect();
+        let reached_end = |sweep: &Sweep| ends.iter().find(|(_, idx)| sweep.rank[*idx] != RankType::MAX).copied();
+
+        let mut next = Vec::new();
+        let mut rank = 0;
+        while reached_end(&sweep).is_none() && !frontier.is_empty() {
+            rank += 1;
+            for idx in frontier.iter() {
+                for axis in [SWEPT_V, SWEPT_H].iter() {
+                    if sweep.swept[*idx] & axis == 0 {
+                        sweep.sweep(self, *idx, *axis, rank, &mut next);
+                    }
+                }
+            }
+            std::mem::swap(&mut frontier, &mut next);
+            next.clear();
+        }
+
+        let (end_pos, end) = reached_end(&sweep)?;
+        let mut path = VecDeque::new();
+        if with_path {
+            path.push_front(self.rc(end));
+        }
+        // Path is followed also without with_path, to find which start it begins with
+        let mut idx = end;
+        let mut path_len = 0;
+        while sweep.prev[idx] as usize != idx {
+            let prev_idx = sweep.prev[idx] as usize;
+            if with_path {
+                let (r, c) = self.rc(idx);
+                let (prev_r, prev_c) = self.rc(prev_idx);
+                // Fill in the straight segment between prev_idx and idx
+                while path[0] != (prev_r, prev_c) {
+                    let (r1, c1) = path[0];
+                    let step =
+                        if r == prev_r {
+                            if c1 > prev_c { (r1, c1 - 1) } else { (r1, c1 + 1) }
+                        } else if r1 > prev_r { (r1 - 1, c1) } else { (r1 + 1, c1) };
+                    path.push_front(step);
+                }
+                debug_assert!(r == prev_r || c == prev_c, "Logical error - segment should be straight");
+            }
+            idx = prev_idx;
+            path_len += 1;
+            if path_len > self.data.len() {
+                panic!("Something wrong - Cycle detected");
+            }
+        }
+        let start_node = self.rc(idx);
+        Some(MultiSolution {
+            rank: sweep.rank[end],
+            start: starts.iter().position(|(node, _)| *node == start_node).expect("Logical error - path should begin at start"),
+            end: end_pos,
+            path,
+        })
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use ndarray::array;
+    use ndarray::Array;
+    use ndarray_rand::RandomExt;
+    use ndarray_rand::rand_distr::Uniform;
+    use super::*;
+    use crate::tests::{init_from_rand_array, count_turns};
+
+    #[test]
+    fn solve_book() {
+        let arr = array![
+            [0,0,0,0,0,0,0,0,0u8],
+            [1,1,1,1,0,1,1,1,0u8],
+            [0,1,0,1,0,1,0,1,0u8],
+            [0,1,0,1,1,1,0,1,0u8],
+            [0,1,0,0,0,0,0,1,0u8],
+            [0,1,1,1,1,0,0,1,0u8],
+            [0,1,0,0,1,1,1,1,1u8],
+            [0,0,0,0,0,0,0,0,0u8],
+        ];
+        let adim = arr.shape();
+        let mut solv = Solver1::new_rc(adim[0] as DimType, adim[1] as DimType).unwrap();
+        for (c, v) in arr.indexed_iter() {
+            if *v == 1 {
+                solv.set_passage(c.0 as DimType, c.1 as DimType);
+            }
+        }
+        assert!(solv.is_valid().is_ok());
+        assert_eq!(
+            format!("{:?}", solv.solve(true).unwrap()),
+            format!("{:?}", (4, vec![(1,0),(1,1),(2,1),(3,1),(4,1),(5,1),(5,2),(5,3),(5,4),(6,4),(6,5),(6,6),(6,7),(6,8)]))
+        );
+    }
+
+    #[test]
+    fn solve_no_solution() {
+        let arr = array![
+            [0,0,0,0,0,0,0,0,0u8],
+            [1,1,1,1,0,1,1,1,0u8],
+            [0,1,0,1,0,1,0,1,0u8],
+            [0,1,0,1,1,1,0,1,0u8],
+            [0,1,0,1,0,0,0,0,0u8],
+            [0,1,1,1,0,0,0,1,0u8],
+            [0,1,0,0,1,1,1,1,1u8],
+            [0,0,0,0,0,0,0,0,0u8],
+        ];
+        let adim = arr.shape();
+        let mut solv = Solver1::new_rc(adim[0] as DimType, adim[1] as DimType).unwrap();
+        for (c, v) in arr.indexed_iter() {
+            if *v == 1 {
+                solv.set_passage(c.0 as DimType, c.1 as DimType);
+            }
+        }
+        assert!(solv.is_valid().is_ok());
+        assert_eq!(solv.solve(false), None );
+    }
+
+    #[test]
+    fn solve_straight() {
+        let mut solv = Solver1::new_rc(3, 4).unwrap();
+        for c in 0..4 {
+            solv.set_passage(1, c);
+        }
+        assert_eq!(
+            format!("{:?}", solv.solve(true).unwrap()),
+            format!("{:?}", (0, vec![(1,0),(1,1),(1,2),(1,3)]))
+        );
+    }
+
+    #[test]
+    fn cross_check() {
+        let mut rng = rand::thread_rng();
+        let r_dim = rng.gen_range(5, 40);
+        let c_dim = rng.gen_range(5, 40);
+        let arr = Array::random((r_dim, c_dim), Uniform::new_inclusive(0, 100));
+        let mut solv = Solver1::new_rc(r_dim as DimType, c_dim as DimType).unwrap();
+        let mut solv_bfs = bfs01::Solver1::new_rc(r_dim as DimType, c_dim as DimType).unwrap();
+        init_from_rand_array(&mut solv, &arr.view(), 75);
+        init_from_rand_array(&mut solv_bfs, &arr.view(), 75);
+        let solution = solv.solve(true);
+        assert_eq!(solution.as_ref().map(|s| s.0), solv_bfs.solve(false).map(|s| s.0));
+        if let Some((rank, path)) = solution {
+            assert_eq!(count_turns(&path), rank);
+            assert_eq!(path.front(), Some(&solv.start));
+            assert_eq!(path.back(), Some(&solv.end));
+        }
+    }
+}
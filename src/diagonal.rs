@@ -0,0 +1,183 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+use super::*;
+use maze::Maze;
+
+/// Whether diagonal move may squeeze between two walls
+/// (both nodes adjacent to the move orthogonally are not passages)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CornerCutting {
+    Allow,
+    Forbid,
+}
+
+/// Options of 8-connected movement used by `solve_diagonal`:
+/// besides 4 orthogonal neighbors, also 4 diagonal ones are reachable in one move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EightConnected {
+    /// Costs of turn by 45, 90, 135 and 180 degrees
+    pub turn_costs: [u64; 4],
+    pub corner_cutting: CornerCutting,
+}
+
+/// Any change of heading is one turn, squeezing between walls is forbidden
+impl Default for EightConnected {
+    fn default() -> Self {
+        EightConnected { turn_costs: [1, 1, 1, 1], corner_cutting: CornerCutting::Forbid }
+    }
+}
+
+impl EightConnected {
+    /// Turns weighted by angle: turn by 45 degrees costs 1, by 90 degrees 2 and so on
+    pub fn angle_weighted(corner_cutting: CornerCutting) -> Self {
+        EightConnected { turn_costs: [1, 2, 3, 4], corner_cutting }
+    }
+}
+
+/// Row and column change of move in each of 8 headings, clockwise starting from up
+const MOVES: [(i32, i32); 8] = [(-1, 0), (-1, 1), (0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1)];
+
+/// Finds the cheapest way from `start` to `end` with 8-connected movement.
+/// Returns cost and the way (empty if not requested),
+/// or `None` also if start or end point is not a passage of the maze.
+///
+/// Search runs on (node, heading) states like `dijkstra_dir`, with 8 headings.
+/// Turn costs are arbitrary, so plain Dijkstra with binary heap is used.
+pub fn solve_diagonal(maze: &Maze, start: NodeAdr, end: NodeAdr, options: &EightConnected, with_path: bool) -> Option<(u64, VecDeque<NodeAdr>)> {
+    if maze.check_endpoints(start, end).is_err() { return None; }
+    let (r_dim, c_dim) = (maze.rows(), maze.cols());
+    let c_dim_u = c_dim as usize;
+    let idx = |(r, c): NodeAdr| r as usize * c_dim_u + c as usize;
+    let rc = |idx: usize| ((idx / c_dim_u) as DimType, (idx % c_dim_u) as DimType);
+    let passage = |r: i32, c: i32| r >= 0 && c >= 0 && r < r_dim as i32 && c < c_dim as i32 && maze.is_passage(r as DimType, c as DimType);
+    // Cost of turn by given number of 45 degrees steps (in either direction)
+    let turn_cost = |from: usize, to: usize| {
+        match (to + 8 - from) % 8 {
+            0 => 0,
+            diff => options.turn_costs[diff.min(8 - diff) - 1],
+        }
+    };
+    // State = node index * 8 + heading
+    let len = r_dim as usize * c_dim_u * 8;
+    let mut cost = vec![u64::MAX; len];
+    let mut prev = vec![usize::MAX; len];
+    let mut todo = BinaryHeap::new();
+    // Start node was not entered from anywhere, so first move in any direction is not a turn
+    for heading in 0..8 {
+        let state = idx(start) * 8 + heading;
+        cost[state] = 0;
+        prev[state] = state;
+        todo.push(Reverse((0, state)));
+    }
+    let mut end_state = None;
+    while let Some(Reverse((state_cost, state))) = todo.pop() {
+        if cost[state] < state_cost { continue; }
+        let (r, c) = rc(state / 8);
+        if (r, c) == end {
+            end_state = Some(state);
+            break;
+        }
+        let (r, c) = (r as i32, c as i32);
+        for (heading, (dr, dc)) in MOVES.iter().enumerate() {
+            if !passage(r + dr, c + dc) { continue; }
+            if *dr != 0 && *dc != 0 && options.corner_cutting == CornerCutting::Forbid
+                && !passage(r + dr, c) && !passage(r, c + dc) { continue; }
+            let next = idx(((r + dr) as DimType, (c + dc) as DimType)) * 8 + heading;
+            let next_cost = state_cost + turn_cost(state % 8, heading);
+            if next_cost < cost[next] {
+                cost[next] = next_cost;
+                prev[next] = state;
+                todo.push(Reverse((next_cost, next)));
+            }
+        }
+    }
+
+    let end_state = end_state?;
+    let mut path = VecDeque::new();
+    if with_path {
+        let mut state = end_state;
+        path.push_front(rc(state / 8));
+        while prev[state] != state {
+            state = prev[state];
+            path.push_front(rc(state / 8));
+        }
+    }
+    Some((cost[end_state], path))
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{rand_maze, reference_rank};
+
+    #[test]
+    fn corner_cutting() {
+        // Diagonal line between walls and then one step right
+        let mut maze = Maze::new_rc(6, 5);
+        for node in [(1, 0), (2, 1), (3, 2), (4, 3), (4, 4)].iter() {
+            maze.set_passage(node.0, node.1);
+        }
+        let (start, end) = default_endpoints(6, 5);
+        let options = EightConnected { corner_cutting: CornerCutting::Allow, ..EightConnected::default() };
+        assert_eq!(
+            solve_diagonal(&maze, start, end, &options, true),
+            Some((1, vec![(1, 0), (2, 1), (3, 2), (4, 3), (4, 4)].into_iter().collect()))
+        );
+        assert_eq!(solve_diagonal(&maze, start, end, &EightConnected::angle_weighted(CornerCutting::Allow), false).map(|s| s.0), Some(1));
+        assert_eq!(solve_diagonal(&maze, start, end, &EightConnected::default(), false), None);
+        assert_eq!(reference_rank(&maze), None);
+    }
+
+    #[test]
+    fn angle_weights() {
+        // Corridor with 90 degrees corner, which may be cut diagonally (only one side of the cut is wall)
+        let mut maze = Maze::new_rc(6, 4);
+        for c in 0..4 {
+            maze.set_passage(1, c);
+        }
+        for r in 2..5 {
+            maze.set_passage(r, 3);
+        }
+        let (start, end) = default_endpoints(6, 4);
+        assert_eq!(solve_diagonal(&maze, start, end, &EightConnected::default(), false).map(|s| s.0), Some(1));
+        assert_eq!(solve_diagonal(&maze, start, end, &EightConnected::angle_weighted(CornerCutting::Forbid), false).map(|s| s.0), Some(2));
+        let options = EightConnected { turn_costs: [1, 3, 3, 3], corner_cutting: CornerCutting::Forbid };
+        assert_eq!(
+            solve_diagonal(&maze, start, end, &options, true),
+            Some((2, vec![(1, 0), (1, 1), (1, 2), (2, 3), (3, 3), (4, 3)].into_iter().collect()))
+        );
+    }
+
+    #[test]
+    fn cross_check() {
+        let maze = rand_maze(40, 60);
+        let (start, end) = default_endpoints(maze.rows(), maze.cols());
+        let is_passage = |node: &NodeAdr| maze.is_passage(node.0, node.1);
+        let solution = solve_diagonal(&maze, start, end, &EightConnected::default(), true);
+        // Way with 4 neighbors is also a way with 8 ones
+        if let Some(rank) = reference_rank(&maze) {
+            assert!(solution.as_ref().unwrap().0 <= rank as u64);
+        }
+        if let Some((cost, path)) = solution {
+            let mut turns = 0;
+            for i in 1..path.len() {
+                let (a, b) = (path[i - 1], path[i]);
+                let (dr, dc) = (b.0 as i32 - a.0 as i32, b.1 as i32 - a.1 as i32);
+                assert!(dr.abs() <= 1 && dc.abs() <= 1 && (dr, dc) != (0, 0));
+                assert!(is_passage(&b));
+                if dr != 0 && dc != 0 {
+                    assert!(is_passage(&(b.0, a.1)) || is_passage(&(a.0, b.1)), "squeezing between walls");
+                }
+                if i > 1 && (dr, dc) != (a.0 as i32 - path[i - 2].0 as i32, a.1 as i32 - path[i - 2].1 as i32) {
+                    turns += 1;
+                }
+            }
+            assert_eq!(turns, cost);
+        }
+    }
+}
@@ -0,0 +1,673 @@
+use std::collections::{HashMap, VecDeque};
+
+use num::{BigUint, One, Zero};
+
+use super::*;
+
+/// To store number of turns
+type RankType = DimType;
+
+/// Node of the search graph: maze node together with heading by which it was entered.
+/// The cost of leaving a node depends on the heading, so the same maze node reached
+/// from different directions with equal rank has to be kept as separate states.
+type StateAdr = (NodeAdr, Heading);
+
+#[derive(Clone)]
+struct Nodedata {
+    rank: RankType,
+    prev: Option<StateAdr>,
+}
+
+pub struct Solver1 {
+    data: Vec<u8>,
+    r_dim: DimType,
+    c_dim: DimType,
+    start: NodeAdr,
+    end: NodeAdr,
+    reversal: Reversal,
+}
+
+impl Solver1 {
+
+    pub fn new_rc(r: DimType, c:DimType) -> Result<Self, Error> {
+        let (start, end) = default_endpoints(r, c);
+        Self::new_with_endpoints(r, c, start, end)
+    }
+
+    /// Creates solver for maze with given start and end points
+    pub fn new_with_endpoints(r: DimType, c:DimType, start: NodeAdr, end: NodeAdr) -> Result<Self, Error> {
+        if r < 2 || c < 2 { return Err(Error::TooSmallDimensions { rows:r, cols:c }); }
+        // Rough estimation of maximal number of turns
+        // If this is too taught, set RankType to usize
+        if (r as usize + c as usize) / 2 > RankType::MAX as usize { return Err(Error::TooBigDimensions { rows:r, cols:c }); }
+        check_endpoints(r, c, start, end)?;
+        Ok(Solver1 {
+            data: vec![0; r as usize * c as usize],
+            r_dim:r,
+            c_dim:c,
+            start,
+            end,
+            reversal: Reversal::default(),
+        })
+    }
+
+    /// Sets how moving back to the previous node is counted by `solve`, `solve_multi` and `optimal_paths`
+    /// (by default as one turn)
+    pub fn set_reversal(&mut self, reversal: Reversal) {
+        self.reversal = reversal;
+    }
+
+    /// Rank increase of the move with given heading from state with given heading,
+    /// or `None` if such move is not allowed
+    #[inline]
+    fn move_rank(&self, from: Heading, to: Heading) -> Option<RankType> {
+        if to == from {
+            // continue moving in the same direction
+            Some(0)
+        } else if to == from.opposite() {
+            match self.reversal {
+                Reversal::Turn => Some(1),
+                Reversal::DoubleTurn => Some(2),
+                Reversal::Forbidden => None,
+            }
+        } else {
+            // turn
+            Some(1)
+        }
+    }
+
+    /// Finds all ways from start to end with minimal number of turns.
+    /// Returns `None` if there is no way, or start or end point is not a passage.
+    pub fn optimal_paths(&self) -> Option<OptimalPaths> {
+        if self.is_valid().is_err() { return None; }
+        let mut purgatory = HashMap::<StateAdr, Nodedata>::new();
+        for heading in Heading::ALL.iter() {
+            purgatory.insert((self.start, *heading), Nodedata {rank:0, prev:None});
+        }
+        let (heaven, end_state) = self.search(purgatory, |node| *node == self.end, true);
+        let rank = heaven[&end_state?].rank;
+
+        // Optimal ways finish in any end state with minimal rank.
+        // If start is the end, all its states represent the same (empty) way.
+        let mut ends: Vec<StateAdr> = Heading::ALL.iter()
+            .map(|heading| (self.end, *heading))
+            .filter(|state| heaven.get(state).map(|data| data.rank) == Some(rank))
+            .collect();
+        if self.start == self.end { ends.truncate(1); }
+
+        // Collect states lying on optimal ways together with their predecessors on these ways:
+        // states from which the move costs exactly the difference of ranks.
+        // Such moves either continue in the same direction with equal rank, or turn and increase rank,
+        // so they form acyclic graph - the only states without predecessors are start states.
+        let mut preds = HashMap::<StateAdr, Vec<StateAdr>>::new();
+        let mut todo = ends.clone();
+        while let Some(state) = todo.pop() {
+            if preds.contains_key(&state) { continue; }
+            let state_rank = heaven[&state].rank;
+            let ((r, c), heading) = state;
+            let prev_node = match heading {
+                Heading::Up => (r.wrapping_add(1), c),
+                Heading::Right => (r, c.wrapping_sub(1)),
+                Heading::Down => (r.wrapping_sub(1), c),
+                Heading::Left => (r, c.wrapping_add(1)),
+            };
+            let state_preds: Vec<StateAdr> = Heading::ALL.iter()
+                .map(|prev_heading| (prev_node, *prev_heading))
+                .filter(|prev| match (heaven.get(prev), self.move_rank(prev.1, heading)) {
+                    (Some(prev_data), Some(move_rank)) => prev_data.rank + move_rank == state_rank,
+                    _ => false,
+                })
+                .collect();
+            todo.extend(state_preds.iter());
+            preds.insert(state, state_preds);
+        }
+
+        // Number of ways leading to every state: states are visited in topological order -
+        // by rank and then by progress in the direction of their heading.
+        let mut order: Vec<StateAdr> = preds.keys().copied().collect();
+        order.sort_by_key(|((r, c), heading)| {
+            let progress = match heading {
+                Heading::Up => -(*r as i32),
+                Heading::Right => *c as i32,
+                Heading::Down => *r as i32,
+                Heading::Left => -(*c as i32),
+            };
+            (heaven[&((*r, *c), *heading)].rank, *heading as u8, progress)
+        });
+        let mut counts = HashMap::<StateAdr, BigUint>::new();
+        for state in order {
+            let state_preds = &preds[&state];
+            let count =
+                if state_preds.is_empty() { BigUint::one() }
+                else { state_preds.iter().fold(BigUint::zero(), |sum, prev| sum + &counts[prev]) };
+            counts.insert(state, count);
+        }
+        let count = ends.iter().fold(BigUint::zero(), |sum, end| sum + &counts[end]);
+
+        Some(OptimalPaths { rank, count, preds, ends, end_pos:0, stack:Vec::new() })
+    }
+
+    /// Dijkstra search from states initially put in `purgatory`, until the first state in end node is settled.
+    /// If `whole_rank` is set, search continues until all states with rank of the end state are settled.
+    /// Returns all settled states and the end state (if any).
+    fn search(&self, mut purgatory: HashMap<StateAdr, Nodedata>, is_end: impl Fn(&NodeAdr) -> bool, whole_rank: bool)
+        -> (HashMap<StateAdr, Nodedata>, Option<StateAdr>)
+    {
+        let mut heaven = HashMap::<StateAdr, Nodedata>::new();
+        let mut end_state = None;
+        loop {
+            // Find todo state with minimal rank
+            let mut min_val = RankType::MAX;
+            let mut min_state = None;
+            for (state, state_data) in purgatory.iter() {
+                if state_data.rank < min_val {
+                    min_val = state_data.rank;
+                    min_state = Some(*state);
+                }
+            }
+
+            // If list of non fixed states is empty or we arrived into end node (and finished its rank) - exit loop
+            let cur_state = match min_state {
+                Some(min_state) => min_state,
+                None => break,
+            };
+            if let Some(end_state) = end_state {
+                if min_val > heaven[&end_state].rank { break; }
+            }
+            let cur_state_data = purgatory.remove(&cur_state).expect("Logical error - min_state should be in the purgatory");
+            heaven.insert(cur_state, cur_state_data.clone());
+            if end_state.is_none() && is_end(&cur_state.0) {
+                end_state = Some(cur_state);
+                if !whole_rank { break; }
+            }
+
+            // Evaluate rate of neighbor states
+            for state in self.neighbors(cur_state.0) {
+                if heaven.contains_key(&state) { continue; }
+                let rank = match self.move_rank(cur_state.1, state.1) {
+                    Some(move_rank) => cur_state_data.rank + move_rank,
+                    None => continue,
+                };
+                if let Some(state_data) = purgatory.get_mut(&state) {
+                    if state_data.rank > rank {
+                        state_data.rank = rank;
+                        state_data.prev = Some(cur_state);
+                    }
+                } else {
+                    purgatory.insert(state, Nodedata {rank, prev:Some(cur_state)});
+                }
+            }
+        }
+
+        (heaven, end_state)
+    }
+
+    #[inline]
+    fn idx(&self, r: DimType, c:DimType) -> usize {
+        r as usize * self.c_dim as usize + c as usize
+    }
+
+    #[inline]
+    fn is_passage(&self, r: DimType, c:DimType) -> bool {
+        self.data[self.idx(r, c)] == 1
+    }
+
+    #[inline]
+    fn neighbors(&self, rc: NodeAdr) -> NodeIter<'_> {
+        NodeIter { solver:self, r:rc.0, c:rc.1, mode:0 }
+    }
+
+}
+
+/// Iterates over neighbor passages together with heading of the move leading to them
+struct NodeIter<'a> {
+    solver: &'a Solver1,
+    r: DimType,
+    c: DimType,
+    mode: u8
+}
+
+impl Iterator for NodeIter<'_> {
+    type Item = StateAdr;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.mode {
+                0 => {
+                    self.mode = 1;
+                    if self.r > 0 && self.solver.is_passage(self.r - 1, self.c) {
+                        return Some(((self.r - 1, self.c), Heading::Up));
+                    }
+                },
+                1 => {
+                    self.mode = 2;
+                    if self.c < self.solver.c_dim - 1 && self.solver.is_passage(self.r, self.c + 1) {
+                        return Some(((self.r, self.c + 1), Heading::Right));
+                    }
+                },
+                2 => {
+                    self.mode = 3;
+                    if self.r < self.solver.r_dim - 1 && self.solver.is_passage(self.r + 1, self.c) {
+                        return Some(((self.r + 1, self.c), Heading::Down));
+                    }
+                },
+                3 => {
+                    self.mode = 4;
+                    if self.c > 0 && self.solver.is_passage(self.r, self.c - 1) {
+                        return Some(((self.r, self.c - 1), Heading::Left));
+                    }
+                },
+                _ => { return None; }
+            }
+        }
+    }
+}
+
+/// All ways from start to end with minimal number of turns, found by `Solver1::optimal_paths`.
+/// Iterates over the ways lazily, each one is built only when requested.
+pub struct OptimalPaths {
+    rank: RankType,
+    count: BigUint,
+    /// States on optimal ways with their predecessors
+    preds: HashMap<StateAdr, Vec<StateAdr>>,
+    ends: Vec<StateAdr>,
+    /// Next state in `ends` to start from
+    end_pos: usize,
+    /// Current way followed backward from end state, with position of the next predecessor to visit
+    stack: Vec<(StateAdr, usize)>,
+}
+
+impl OptimalPaths {
+    /// Number of turns of every way
+    pub fn rank(&self) -> DimType {
+        self.rank
+    }
+
+    /// Number of distinct ways
+    pub fn num_paths(&self) -> &BigUint {
+        &self.count
+    }
+}
+
+impl Iterator for OptimalPaths {
+    type Item = VecDeque<NodeAdr>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.last_mut() {
+                None => {
+                    let end = *self.ends.get(self.end_pos)?;
+                    self.end_pos += 1;
+                    self.stack.push((end, 0));
+                },
+                Some((state, pos)) => {
+                    let state_preds = &self.preds[state];
+                    if state_preds.is_empty() {
+                        // Start state reached
+                        let path = self.stack.iter().rev().map(|(state, _)| state.0).collect();
+                        self.stack.pop();
+                        return Some(path);
+                    } else if *pos < state_preds.len() {
+                        let prev = state_preds[*pos];
+                        *pos += 1;
+                        self.stack.push((prev, 0));
+                    } else {
+                        self.stack.pop();
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Solver for Solver1 {
+
+    fn set_passage(&mut self, r: DimType, c: DimType) {
+        let idx = self.idx(r, c);
+        self.data[idx] = 1;
+    }
+
+    fn is_valid(&self) -> Result<(), Error> {
+        if !self.is_passage(self.start.0, self.start.1) { return Err(Error::StartBlocked(self.start)); }
+        if !self.is_passage(self.end.0, self.end.1) { return Err(Error::EndBlocked(self.end)); }
+        Ok(())
+    }
+
+    fn solve(&self, with_path: bool) -> Option<(DimType, VecDeque<NodeAdr>)> {
+        if self.is_valid().is_err() { return None; }
+        self.solve_multi(&[(self.start, None)], &[self.end], with_path)
+            .map(|solution| (solution.rank, solution.path))
+    }
+
+    fn solve_multi(&self, starts: &[StartPoint], ends: &[NodeAdr], with_path: bool) -> Option<MultiSolution> {
+        let is_valid_node = |node: &NodeAdr| node.0 < self.r_dim && node.1 < self.c_dim && self.is_passage(node.0, node.1);
+        let mut start_idx = HashMap::<NodeAdr, usize>::new();
+        let mut end_idx = HashMap::<NodeAdr, usize>::new();
+        for (idx, node) in ends.iter().enumerate().filter(|(_, node)| is_valid_node(node)) {
+            end_idx.entry(*node).or_insert(idx);
+        }
+
+        let mut purgatory = HashMap::<StateAdr, Nodedata>::new();
+        for (idx, (node, heading)) in starts.iter().enumerate().filter(|(_, (node, _))| is_valid_node(node)) {
+            if start_idx.contains_key(node) { continue; }
+            start_idx.insert(*node, idx);
+            match heading {
+                Some(heading) => {
+                    purgatory.insert((*node, *heading), Nodedata {rank:0, prev:None});
+                },
+                None => {
+                    // Start node was not entered from anywhere, so first move in any direction is not a turn.
+                    // This is modeled by putting start node with every possible heading into purgatory.
+                    for heading in Heading::ALL.iter() {
+                        purgatory.insert((*node, *heading), Nodedata {rank:0, prev:None});
+                    }
+                }
+            }
+        }
+        let (heaven, end_state) = self.search(purgatory, |node| end_idx.contains_key(node), false);
+
+        let end_state = end_state?;
+        let mut path = VecDeque::new();
+        // Path is followed also without with_path, to find which start it begins with
+        let mut state = end_state;
+        let mut path_len = 0;
+        loop {
+            if with_path {
+                path.push_front(state.0);
+            }
+            match heaven.get(&state).expect("Logical error - every state on path should be in heaven").prev {
+                Some(prev) => state = prev,
+                None => break,
+            }
+            path_len += 1;
+            if path_len > heaven.len() {
+                panic!("Something wrong - Cycle detected");
+            }
+        }
+        Some(MultiSolution {
+            rank: heaven[&end_state].rank,
+            start: start_idx[&state.0],
+            end: end_idx[&end_state.0],
+            path,
+        })
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use ndarray::array;
+    use ndarray::Array;
+    use ndarray_rand::RandomExt;
+    use ndarray_rand::rand_distr::Uniform;
+    use super::*;
+    use crate::tests::{init_from_rand_array, count_turns};
+
+    #[test]
+    fn neighbors() {
+        let arr = array![
+            [0,0,1,0,0u8],
+            [1,1,1,1,0u8],
+            [0,1,1,0,0u8],
+            [0,0,0,1,1u8],
+        ];
+        let adim = arr.shape();
+        let mut solv = Solver1::new_rc(adim[0] as DimType, adim[1] as DimType).unwrap();
+        for (c, v) in arr.indexed_iter() {
+            if *v == 1 {
+                solv.set_passage(c.0 as DimType, c.1 as DimType);
+            }
+        }
+        assert_eq!(solv.neighbors((1, 1)).collect::<Vec<_>>(), vec![((1, 2), Heading::Right), ((2, 1), Heading::Down), ((1, 0), Heading::Left)]);
+        assert_eq!(solv.neighbors((0, 2)).collect::<Vec<_>>(), vec![((1, 2), Heading::Down)]);
+        assert_eq!(solv.neighbors((3, 0)).collect::<Vec<_>>(), vec![]);
+    }
+
+    #[test]
+    fn solve_book() {
+        let arr = array![
+            [0,0,0,0,0,0,0,0,0u8],
+            [1,1,1,1,0,1,1,1,0u8],
+            [0,1,0,1,0,1,0,1,0u8],
+            [0,1,0,1,1,1,0,1,0u8],
+            [0,1,0,0,0,0,0,1,0u8],
+            [0,1,1,1,1,0,0,1,0u8],
+            [0,1,0,0,1,1,1,1,1u8],
+            [0,0,0,0,0,0,0,0,0u8],
+        ];
+        let adim = arr.shape();
+        let mut solv = Solver1::new_rc(adim[0] as DimType, adim[1] as DimType).unwrap();
+        for (c, v) in arr.indexed_iter() {
+            if *v == 1 {
+                solv.set_passage(c.0 as DimType, c.1 as DimType);
+            }
+        }
+        assert!(solv.is_valid().is_ok());
+        assert_eq!(
+            format!("{:?}", solv.solve(true).unwrap()),
+            format!("{:?}", (4, vec![(1,0),(1,1),(2,1),(3,1),(4,1),(5,1),(5,2),(5,3),(5,4),(6,4),(6,5),(6,6),(6,7),(6,8)]))
+        );
+    }
+
+    #[test]
+    fn solve_no_solution() {
+        let arr = array![
+            [0,0,0,0,0,0,0,0,0u8],
+            [1,1,1,1,0,1,1,1,0u8],
+            [0,1,0,1,0,1,0,1,0u8],
+            [0,1,0,1,1,1,0,1,0u8],
+            [0,1,0,1,0,0,0,0,0u8],
+            [0,1,1,1,0,0,0,1,0u8],
+            [0,1,0,0,1,1,1,1,1u8],
+            [0,0,0,0,0,0,0,0,0u8],
+        ];
+        let adim = arr.shape();
+        let mut solv = Solver1::new_rc(adim[0] as DimType, adim[1] as DimType).unwrap();
+        for (c, v) in arr.indexed_iter() {
+            if *v == 1 {
+                solv.set_passage(c.0 as DimType, c.1 as DimType);
+            }
+        }
+        assert!(solv.is_valid().is_ok());
+        assert_eq!(solv.solve(false), None );
+    }
+
+    #[test]
+    fn solve_tie_with_different_heading() {
+        // Node (3,1) is reached with rank 1 both by turn from (3,0) and by moving down from (2,1).
+        // Only the latter allows to continue down to (4,1) without a turn.
+        let arr = array![
+            [0,0,0,0u8],
+            [1,1,0,0u8],
+            [1,1,0,0u8],
+            [1,1,0,0u8],
+            [0,1,0,1u8],
+            [0,1,1,1u8],
+        ];
+        let adim = arr.shape();
+        let mut solv = Solver1::new_rc(adim[0] as DimType, adim[1] as DimType).unwrap();
+        let mut solv_speed = dijkstra_speed::Solver1::new_rc(adim[0] as DimType, adim[1] as DimType).unwrap();
+        for (c, v) in arr.indexed_iter() {
+            if *v == 1 {
+                solv.set_passage(c.0 as DimType, c.1 as DimType);
+                solv_speed.set_passage(c.0 as DimType, c.1 as DimType);
+            }
+        }
+        assert!(solv.is_valid().is_ok());
+        assert_eq!(
+            format!("{:?}", solv.solve(true).unwrap()),
+            format!("{:?}", (3, vec![(1,0),(1,1),(2,1),(3,1),(4,1),(5,1),(5,2),(5,3),(4,3)]))
+        );
+        // Solver which keeps single state per node settles (3,1) as entered from the left
+        // and therefore counts additional turn at that node.
+        assert_eq!(solv_speed.solve(false).unwrap().0, 4);
+    }
+
+    #[test]
+    fn cross_check() {
+        let mut rng = rand::thread_rng();
+        let r_dim = rng.gen_range(5, 20);
+        let c_dim = rng.gen_range(5, 20);
+        let arr = Array::random((r_dim, c_dim), Uniform::new_inclusive(0, 100));
+        let mut solv = Solver1::new_rc(r_dim as DimType, c_dim as DimType).unwrap();
+        let mut solv_speed = dijkstra_speed::Solver1::new_rc(r_dim as DimType, c_dim as DimType).unwrap();
+        init_from_rand_array(&mut solv, &arr.view(), 75);
+        init_from_rand_array(&mut solv_speed, &arr.view(), 75);
+        match (solv.solve(false), solv_speed.solve(false)) {
+            (Some(solution), Some(solution_speed)) => assert!(solution.0 <= solution_speed.0),
+            (None, None) => {},
+            _ => panic!("Solvers do not agree on existence of solution"),
+        }
+    }
+
+    #[test]
+    fn optimal_paths() {
+        let arr = array![
+            [0,0,0,0,0u8],
+            [1,1,1,1,0u8],
+            [0,1,0,1,0u8],
+            [0,1,1,1,1u8],
+            [0,0,0,0,0u8],
+        ];
+        let adim = arr.shape();
+        let mut solv = Solver1::new_rc(adim[0] as DimType, adim[1] as DimType).unwrap();
+        for (c, v) in arr.indexed_iter() {
+            if *v == 1 {
+                solv.set_passage(c.0 as DimType, c.1 as DimType);
+            }
+        }
+        let paths = solv.optimal_paths().unwrap();
+        assert_eq!(paths.rank(), 2);
+        assert_eq!(*paths.num_paths(), BigUint::from(2u32));
+        let mut paths: Vec<Vec<NodeAdr>> = paths.map(|path| path.into_iter().collect()).collect();
+        paths.sort();
+        assert_eq!(paths, vec![
+            vec![(1,0),(1,1),(1,2),(1,3),(2,3),(3,3),(3,4)],
+            vec![(1,0),(1,1),(2,1),(3,1),(3,2),(3,3),(3,4)],
+        ]);
+
+        let mut solv = Solver1::new_with_endpoints(3, 3, (1, 1), (1, 1)).unwrap();
+        solv.set_passage(1, 1);
+        solv.set_passage(1, 2);
+        let paths = solv.optimal_paths().unwrap();
+        assert_eq!(*paths.num_paths(), BigUint::one());
+        assert_eq!(paths.collect::<Vec<_>>(), vec![vec![(1, 1)].into_iter().collect::<VecDeque<_>>()]);
+
+        let solv = Solver1::new_rc(3, 3).unwrap();
+        assert!(solv.optimal_paths().is_none());
+    }
+
+    /// Counts simple ways from `node` to the end with given number of turns, by checking all of them
+    fn count_brute_force(solv: &Solver1, path: &mut VecDeque<NodeAdr>, rank: DimType) -> u64 {
+        let turns = count_turns(path);
+        if turns > rank { return 0; }
+        let node = *path.back().unwrap();
+        if node == solv.end { return if turns == rank { 1 } else { 0 }; }
+        let mut count = 0;
+        for (next, _) in solv.neighbors(node) {
+            if path.contains(&next) { continue; }
+            path.push_back(next);
+            count += count_brute_force(solv, path, rank);
+            path.pop_back();
+        }
+        count
+    }
+
+    #[test]
+    fn optimal_paths_brute_force() {
+        let mut rng = rand::thread_rng();
+        let r_dim = rng.gen_range(4, 7);
+        let c_dim = rng.gen_range(4, 7);
+        let arr = Array::random((r_dim, c_dim), Uniform::new_inclusive(0, 100));
+        let mut solv = Solver1::new_rc(r_dim as DimType, c_dim as DimType).unwrap();
+        init_from_rand_array(&mut solv, &arr.view(), 70);
+        match solv.solve(false) {
+            None => assert!(solv.optimal_paths().is_none()),
+            Some((rank, _)) => {
+                let paths = solv.optimal_paths().unwrap();
+                assert_eq!(paths.rank(), rank);
+                let count = paths.num_paths().clone();
+                let mut start_path = vec![solv.start].into_iter().collect();
+                assert_eq!(count, BigUint::from(count_brute_force(&solv, &mut start_path, rank)));
+                let mut n = 0u64;
+                for path in paths {
+                    assert_eq!(count_turns(&path), rank);
+                    assert_eq!(path.front(), Some(&solv.start));
+                    assert_eq!(path.back(), Some(&solv.end));
+                    n += 1;
+                }
+                assert_eq!(BigUint::from(n), count);
+            }
+        }
+    }
+
+    #[test]
+    fn reversal() {
+        // Robot starts in the corridor facing its dead end, the only exit is behind it
+        let arr = array![
+            [0,0,0,0,0,0u8],
+            [1,1,1,1,1,0u8],
+            [1,0,0,0,0,0u8],
+            [1,1,1,1,1,1u8],
+        ];
+        let adim = arr.shape();
+        let mut solv = Solver1::new_with_endpoints(adim[0] as DimType, adim[1] as DimType, (1, 2), (3, 5)).unwrap();
+        for (c, v) in arr.indexed_iter() {
+            if *v == 1 {
+                solv.set_passage(c.0 as DimType, c.1 as DimType);
+            }
+        }
+        let starts = [((1, 2), Some(Heading::Right))];
+        let ends = [(3, 5)];
+        assert_eq!(solv.solve(false).map(|s| s.0), Some(2));
+        assert_eq!(solv.solve_multi(&starts, &ends, false).map(|s| s.rank), Some(3));
+
+        solv.set_reversal(Reversal::DoubleTurn);
+        assert_eq!(solv.solve(false).map(|s| s.0), Some(2));
+        let solution = solv.solve_multi(&starts, &ends, true).unwrap();
+        assert_eq!(solution.rank, 4);
+        assert!(solution.path.contains(&(1, 0)));
+
+        solv.set_reversal(Reversal::Forbidden);
+        assert_eq!(solv.solve(false).map(|s| s.0), Some(2));
+        assert_eq!(solv.solve_multi(&starts, &ends, false), None);
+        // Facing the exit it is still possible
+        assert_eq!(solv.solve_multi(&[((1, 2), Some(Heading::Left))], &ends, false).map(|s| s.rank), Some(2));
+        assert_eq!(solv.optimal_paths().map(|paths| paths.count()), Some(1));
+    }
+
+    #[test]
+    fn reversal_dead_end() {
+        // Robot faces the corridor with dead end ahead, the side branch makes a loop back to the exit
+        let arr = array![
+            [1,0,0,0,0u8],
+            [1,1,1,1,1u8],
+            [1,0,0,1,0u8],
+            [1,1,1,1,0u8],
+        ];
+        let adim = arr.shape();
+        let mut solv = Solver1::new_with_endpoints(adim[0] as DimType, adim[1] as DimType, (1, 1), (0, 0)).unwrap();
+        for (c, v) in arr.indexed_iter() {
+            if *v == 1 {
+                solv.set_passage(c.0 as DimType, c.1 as DimType);
+            }
+        }
+        let starts = [((1, 1), Some(Heading::Right))];
+        let ends = [(0, 0)];
+        let solution = solv.solve_multi(&starts, &ends, true).unwrap();
+        assert_eq!(solution.rank, 2);
+        assert_eq!(solution.path[1], (1, 0));
+
+        solv.set_reversal(Reversal::DoubleTurn);
+        assert_eq!(solv.solve_multi(&starts, &ends, false).map(|s| s.rank), Some(3));
+
+        solv.set_reversal(Reversal::Forbidden);
+        let solution = solv.solve_multi(&starts, &ends, true).unwrap();
+        assert_eq!(solution.rank, 3);
+        assert!(solution.path.contains(&(3, 3)));
+        let path: Vec<_> = solution.path.into_iter().collect();
+        assert!(path.windows(3).all(|w| w[0] != w[2]));
+    }
+}
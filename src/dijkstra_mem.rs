@@ -11,33 +11,112 @@ pub struct Solver1 {
     c_dim: DimType,
     start: NodeAdr,
     end: NodeAdr,
+    topology: Topology,
+    /// Node => nodes linked with it by portals
+    portals: HashMap<NodeAdr, Vec<NodeAdr>>,
+    portal_cost: RankType,
+    /// One-way node => heading in which it may be left
+    one_way: HashMap<NodeAdr, Heading>,
 }
 
 impl Solver1 {
 
-    pub fn new_rc(r: DimType, c:DimType) -> Result<Self, &'static str> {
-        if r < 2 || c < 2 { return Err("Too small dimensions"); }
+    pub fn new_rc(r: DimType, c:DimType) -> Result<Self, Error> {
+        let (start, end) = default_endpoints(r, c);
+        Self::new_with_endpoints(r, c, start, end)
+    }
+
+    /// Creates solver for maze with given start and end points
+    pub fn new_with_endpoints(r: DimType, c:DimType, start: NodeAdr, end: NodeAdr) -> Result<Self, Error> {
+        if r < 2 || c < 2 { return Err(Error::TooSmallDimensions { rows:r, cols:c }); }
         // Rough estimation of maximal number of turns 
         // If this is too taught, set RankType to usize
-        if (r as usize + c as usize) / 2 > RankType::MAX as usize { return Err("Too big dimensions"); } 
+        if (r as usize + c as usize) / 2 > RankType::MAX as usize { return Err(Error::TooBigDimensions { rows:r, cols:c }); } 
+        check_endpoints(r, c, start, end)?;
         Ok(Solver1 { 
             data: HashSet::new(), 
             r_dim:r, 
             c_dim:c,
-            start: (1,0),
-            end: (r - 2, c - 1),
+            start,
+            end,
+            topology: Topology::default(),
+            portals: HashMap::new(),
+            portal_cost: 0,
+            one_way: HashMap::new(),
         })
     }
 
+    /// Sets shape of the maze borders used by `solve` and `solve_multi` (by default bounded).
+    /// Turns are counted the same way across the seam of toroidal maze, e.g. moving right from the last column
+    /// to the first one continues straight.
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    /// Makes the node one-way passage, which may be left only with given heading (but entered from any direction).
+    /// It is honoured by `solve` and `solve_multi`.
+    pub fn set_one_way(&mut self, r: DimType, c: DimType, heading: Heading) {
+        self.data.insert((r, c));
+        self.one_way.insert((r, c), heading);
+    }
+
+    /// Links two nodes with portal used by `solve` and `solve_multi`: stepping onto one of them allows to move
+    /// to the other one, at the cost set by `set_portal_cost`. The move from the portal exit in any direction is not a turn.
+    /// Portal is used only if both nodes are passages.
+    pub fn add_portal(&mut self, a: NodeAdr, b: NodeAdr) -> Result<(), Error> {
+        check_endpoints(self.r_dim, self.c_dim, a, b)?;
+        self.portals.entry(a).or_default().push(b);
+        self.portals.entry(b).or_default().push(a);
+        Ok(())
+    }
+
+    /// Sets number of turns which the move thru portal counts as (by default 0)
+    pub fn set_portal_cost(&mut self, turns: DimType) {
+        self.portal_cost = turns;
+    }
+
+    /// Nodes linked by portals with given node which are passages
+    fn portal_exits(&self, node: NodeAdr) -> impl Iterator<Item = NodeAdr> + '_ {
+        self.portals.get(&node).into_iter().flatten().copied().filter(move |n| self.is_passage(n.0, n.1))
+    }
+
     #[inline]
     fn is_passage(&self, r: DimType, c:DimType) -> bool {
         self.data.contains(&(r, c))
     }
 
+    /// Heading in which given node may be left, if it is one-way cell
+    #[inline]
+    fn one_way(&self, node: NodeAdr) -> Option<Heading> {
+        self.one_way.get(&node).copied()
+    }
+
     #[inline]
     fn neighbors(&self, rc: NodeAdr) -> NodeIter<'_> {
         NodeIter { solver:&self, r:rc.0, c:rc.1, mode:0 }
     }
+
+    /// Returns neighbor node in given direction if it is a passage (and the move is allowed by one-way cell)
+    #[inline]
+    fn neighbor(&self, (r, c): NodeAdr, heading: Heading) -> Option<NodeAdr> {
+        match self.one_way((r, c)) {
+            Some(one_way) if one_way != heading => return None,
+            _ => {},
+        }
+        let wrap = self.topology == Topology::Toroidal;
+        let node = match heading {
+            Heading::Up if r > 0 => (r - 1, c),
+            Heading::Up if wrap => (self.r_dim - 1, c),
+            Heading::Right if c < self.c_dim - 1 => (r, c + 1),
+            Heading::Right if wrap => (r, 0),
+            Heading::Down if r < self.r_dim - 1 => (r + 1, c),
+            Heading::Down if wrap => (0, c),
+            Heading::Left if c > 0 => (r, c - 1),
+            Heading::Left if wrap => (r, self.c_dim - 1),
+            _ => return None,
+        };
+        if self.is_passage(node.0, node.1) { Some(node) } else { None }
+    }
 }
 
 struct NodeIter<'a> {
@@ -54,26 +133,26 @@ impl Iterator for NodeIter<'_> {
             match self.mode {
                 0 => {
                     self.mode = 1;
-                    if self.r > 0 && self.solver.is_passage(self.r - 1, self.c) {
-                        return Some((self.r - 1, self.c));
+                    if let Some(node) = self.solver.neighbor((self.r, self.c), Heading::Up) {
+                        return Some(node);
                     }
                 },
                 1 => {
                     self.mode = 2;
-                    if self.c < self.solver.c_dim - 1 && self.solver.is_passage(self.r, self.c + 1) {
-                        return Some((self.r, self.c + 1));
+                    if let Some(node) = self.solver.neighbor((self.r, self.c), Heading::Right) {
+                        return Some(node);
                     }
                 },
                 2 => {
                     self.mode = 3;
-                    if self.r < self.solver.r_dim - 1 && self.solver.is_passage(self.r + 1, self.c) {
-                        return Some((self.r + 1, self.c));
+                    if let Some(node) = self.solver.neighbor((self.r, self.c), Heading::Down) {
+                        return Some(node);
                     }
                 },
                 3 => {
                     self.mode = 4;
-                    if self.c > 0 && self.solver.is_passage(self.r, self.c - 1) {
-                        return Some((self.r, self.c - 1));
+                    if let Some(node) = self.solver.neighbor((self.r, self.c), Heading::Left) {
+                        return Some(node);
                     }
                 },
                 _ => { return None; }
@@ -88,118 +167,133 @@ impl Solver for Solver1 {
         self.data.insert((r, c));
     }
 
-    fn is_valid(&self) -> bool {
-        self.is_passage(self.start.0, self.start.1)
-        &&
-        self.is_passage(self.end.0, self.end.1)
+    fn is_valid(&self) -> Result<(), Error> {
+        if !self.is_passage(self.start.0, self.start.1) { return Err(Error::StartBlocked(self.start)); }
+        if !self.is_passage(self.end.0, self.end.1) { return Err(Error::EndBlocked(self.end)); }
+        Ok(())
     }
 
     fn solve(&self, with_path: bool) -> Option<(DimType, VecDeque<NodeAdr>)> {
+        if self.is_valid().is_err() { return None; }
+        self.solve_multi(&[(self.start, None)], &[self.end], with_path)
+            .map(|solution| (solution.rank, solution.path))
+    }
+
+    fn solve_multi(&self, starts: &[StartPoint], ends: &[NodeAdr], with_path: bool) -> Option<MultiSolution> {
         #[derive(Clone)]
         struct Nodedata {
             rank: RankType,
-            prev: NodeAdr,
+            prev: NodeAdr, 
+            // entered thru portal - the next move in any direction is not a turn
+            portal: bool,
         }
         
-        if !self.is_valid() { return None; }
+        let is_valid_node = |node: &NodeAdr| self.is_passage(node.0, node.1);
+        let mut start_idx = HashMap::<NodeAdr, usize>::new();
+        let mut end_idx = HashMap::<NodeAdr, usize>::new();
+        for (idx, node) in ends.iter().enumerate().filter(|(_, node)| is_valid_node(node)) {
+            end_idx.entry(*node).or_insert(idx);
+        }
 
         let mut purgatory = HashMap::<NodeAdr, Nodedata>::new();
         let mut heaven = HashMap::<NodeAdr, Nodedata>::new();
-        let mut cur_node = self.start;
-        let mut cur_node_data = Nodedata {rank:0, prev:cur_node};
-        heaven.insert(cur_node, cur_node_data.clone());
+        for (idx, (node, heading)) in starts.iter().enumerate().filter(|(_, (node, _))| is_valid_node(node)) {
+            if start_idx.contains_key(node) { continue; }
+            start_idx.insert(*node, idx);
+            purgatory.insert(*node, Nodedata {rank:0, prev:start_prev(*node, *heading, self.r_dim, self.c_dim, self.topology), portal:false});
+        }
+        let mut end_node = None;
         loop {
+            // Find todo node with minimal rank
+            // TODO: This loop probably could be improved:
+            // - maybe use some sorted containers
+            // - maybe parallelize
+            let mut min_val = RankType::MAX;
+            let mut min_node = None;
+            for (node, node_data) in purgatory.iter() {
+                if node_data.rank < min_val {
+                    min_val = node_data.rank;
+                    min_node = Some(*node);
+                }
+            }
+
+            // If list of non fixed nodes is empty or we arrived into end node - exit loop
+            let cur_node = match min_node {
+                Some(min_node) => min_node,
+                None => break,
+            };
+            let cur_node_data = purgatory.remove(&cur_node).expect("Logical error - min_node should be in the purgatory");
+            heaven.insert(cur_node, cur_node_data.clone());
+            if end_idx.contains_key(&cur_node) {
+                end_node = Some(cur_node);
+                break;
+            }
+
             // Evaluate rate of neighbor nodes 
             for node in self.neighbors(cur_node) {
-                if let Some(mut node_data) = purgatory.get_mut(&node) {
-                    let (r, c) = cur_node_data.prev;
-                    if node.0 == cur_node.0 && cur_node.0 == r {
-                        // continue moving in vertical direction
+                if let Some(node_data) = purgatory.get_mut(&node) {
+                    if cur_node_data.portal || !is_turn(cur_node_data.prev, cur_node, node) {
+                        // continue moving in vertical or horizontal direction
                         if node_data.rank > cur_node_data.rank {
-                            node_data.rank = cur_node_data.rank;
-                            node_data.prev = cur_node;
-                        }
-                    }
-                    else if node.1 == cur_node.1 && cur_node.1 == c {
-                        // continue moving in horizontal direction
-                        if node_data.rank > cur_node_data.rank {
-                            node_data.rank = cur_node_data.rank;
-                            node_data.prev = cur_node;
+                            *node_data = Nodedata {rank:cur_node_data.rank, prev:cur_node, portal:false};
                         }
                     } else {
                         // turn
                         if node_data.rank > cur_node_data.rank + 1 {
-                            node_data.rank = cur_node_data.rank + 1;
-                            node_data.prev = cur_node;
+                            *node_data = Nodedata {rank:cur_node_data.rank + 1, prev:cur_node, portal:false};
                         }
                     }
-                } 
+                }
                 else if !heaven.contains_key(&node) {
-                    let (r, c) = cur_node_data.prev;
                     let init_rank = 
-                        if node.0 == cur_node.0 && cur_node.0 == r {
-                            // continue moving in vertical direction
-                            cur_node_data.rank
-                        }
-                        else if node.1 == cur_node.1 && cur_node.1 == c {
-                            // continue moving in horizontal direction
+                        if cur_node_data.portal || !is_turn(cur_node_data.prev, cur_node, node) {
+                            // continue moving in vertical or horizontal direction
                             cur_node_data.rank
                         } else {
                             // turn
                             cur_node_data.rank + 1
                         };
-                    purgatory.insert(node, Nodedata {rank:init_rank, prev:cur_node});
+                    purgatory.insert(node, Nodedata {rank:init_rank, prev:cur_node, portal:false});
                 }
             }
 
-            // Find todo node with minimal rank
-            // TODO: This loop probably could be improved:
-            // - maybe use some sorted containers
-            // - maybe parallelize
-            let mut min_val = RankType::MAX;
-            let mut min_node = None;
-            for (node, node_data) in purgatory.iter() {
-                if node_data.rank < min_val {
-                    min_val = node_data.rank;
-                    min_node = Some(*node);
+            // Move thru portal
+            for node in self.portal_exits(cur_node) {
+                let rank = cur_node_data.rank.saturating_add(self.portal_cost);
+                if let Some(node_data) = purgatory.get_mut(&node) {
+                    if node_data.rank > rank {
+                        *node_data = Nodedata {rank, prev:cur_node, portal:true};
+                    }
                 }
-            }
-
-            // If list of non fixed nodes is empty or we arrived into end node - exit loop
-            if let Some(min_node) = min_node {
-                let min_node_data = purgatory.remove(&min_node).expect("Logical error - min_node should be in the purgatory");
-                cur_node = min_node;
-                cur_node_data = min_node_data.clone();
-                heaven.insert(min_node, min_node_data);
-                if min_node == self.end {
-                    break;
+                else if !heaven.contains_key(&node) {
+                    purgatory.insert(node, Nodedata {rank, prev:cur_node, portal:true});
                 }
-            } else {
-                break;
             }
         }
 
-        if let Some(end_node_data) = heaven.get(&self.end) {
-            let mut path = VecDeque::new();
+        let end_node = end_node?;
+        let mut path = VecDeque::new();
+        // Path is followed also without with_path, to find which start it begins with.
+        // Start nodes have rank 0 from the beginning, so they are never reached from other nodes.
+        let mut node = end_node;
+        let mut path_len = 0;
+        loop {
             if with_path {
-                let mut node = self.end;
-                loop {
-                    path.push_front(node);
-                    let node_data = heaven.get(&node).expect("Logical error - every node on path should be in heaven");
-                    if node == self.start { break; }
-                    node = node_data.prev;
-                    if path.len() > heaven.len() {
-                        panic!("Something wrong - Cycle detected");
-                        // eprint!("Something wrong - Cycle detected");
-                        // break;
-                    }
-                }
+                path.push_front(node);
+            }
+            if start_idx.contains_key(&node) { break; }
+            node = heaven.get(&node).expect("Logical error - every node on path should be in heaven").prev;
+            path_len += 1;
+            if path_len > heaven.len() {
+                panic!("Something wrong - Cycle detected");
             }
-            Some((end_node_data.rank, path))
-        } else {
-            assert!(self.data.contains(&self.end));
-            None
         }
+        Some(MultiSolution {
+            rank: heaven[&end_node].rank,
+            start: start_idx[&node],
+            end: end_idx[&end_node],
+            path,
+        })
     }
 
     fn solve_and_drop(mut self, with_path: bool) -> Option<(DimType, VecDeque<NodeAdr>)> {
@@ -207,40 +301,39 @@ impl Solver for Solver1 {
         struct Nodedata {
             rank: RankType,
             prev: NodeAdr,
+            // entered thru portal - the next move in any direction is not a turn
+            portal: bool,
         }
         
-        if !self.is_valid() { return None; }
+        if self.is_valid().is_err() { return None; }
 
         self.data.shrink_to_fit();
         let mut purgatory = HashMap::<NodeAdr, Nodedata>::new();
         let mut heaven = HashMap::<NodeAdr, Nodedata>::new();
         let mut cur_node = self.start;
-        let mut cur_node_data = Nodedata {rank:0, prev:cur_node};
+        let mut cur_node_data = Nodedata {rank:0, prev:cur_node, portal:false};
         self.data.remove(&cur_node);
         heaven.insert(cur_node, cur_node_data.clone());
         loop {
             // Evaluate rate of neighbor nodes 
             for node in self.neighbors(cur_node) {
-                if let Some(mut node_data) = purgatory.get_mut(&node) {
+                if let Some(node_data) = purgatory.get_mut(&node) {
                     let (r, c) = cur_node_data.prev;
-                    if node.0 == cur_node.0 && cur_node.0 == r {
-                        // continue moving in vertical direction
+                    if cur_node_data.portal || (node.0 == cur_node.0 && cur_node.0 == r) {
+                        // continue moving in vertical direction (or in any direction from portal exit)
                         if node_data.rank > cur_node_data.rank {
-                            node_data.rank = cur_node_data.rank;
-                            node_data.prev = cur_node;
+                            *node_data = Nodedata {rank:cur_node_data.rank, prev:cur_node, portal:false};
                         }
                     }
                     else if node.1 == cur_node.1 && cur_node.1 == c {
                         // continue moving in horizontal direction
                         if node_data.rank > cur_node_data.rank {
-                            node_data.rank = cur_node_data.rank;
-                            node_data.prev = cur_node;
+                            *node_data = Nodedata {rank:cur_node_data.rank, prev:cur_node, portal:false};
                         }
                     } else {
                         // turn
                         if node_data.rank > cur_node_data.rank + 1 {
-                            node_data.rank = cur_node_data.rank + 1;
-                            node_data.prev = cur_node;
+                            *node_data = Nodedata {rank:cur_node_data.rank + 1, prev:cur_node, portal:false};
                         }
                     }
                 } else {
@@ -249,8 +342,8 @@ impl Solver for Solver1 {
                     // This is main difference in compare to solve() algorithm
                     let (r, c) = cur_node_data.prev;
                     let init_rank = 
-                        if node.0 == cur_node.0 && cur_node.0 == r {
-                            // continue moving in vertical direction
+                        if cur_node_data.portal || (node.0 == cur_node.0 && cur_node.0 == r) {
+                            // continue moving in vertical direction (or in any direction from portal exit)
                             cur_node_data.rank
                         }
                         else if node.1 == cur_node.1 && cur_node.1 == c {
@@ -260,7 +353,16 @@ impl Solver for Solver1 {
                             // turn
                             cur_node_data.rank + 1
                         };
-                    purgatory.insert(node, Nodedata {rank:init_rank, prev:cur_node});
+                    purgatory.insert(node, Nodedata {rank:init_rank, prev:cur_node, portal:false});
+                }
+            }
+
+            // Move thru portal - exits already in heaven were removed from self.data, so they are skipped
+            for node in self.portal_exits(cur_node) {
+                let rank = cur_node_data.rank.saturating_add(self.portal_cost);
+                match purgatory.get_mut(&node) {
+                    Some(node_data) if node_data.rank <= rank => {},
+                    _ => { purgatory.insert(node, Nodedata {rank, prev:cur_node, portal:true}); },
                 }
             }
 
@@ -382,7 +484,7 @@ mod tests {
                 solv.set_passage(c.0 as DimType, c.1 as DimType);
             }
         }
-        assert_eq!(solv.is_valid(), true);
+        assert_eq!(solv.is_valid().is_ok(), true);
         assert_eq!(
             format!("{:?}", solv.solve(true).unwrap()), 
             format!("{:?}", (4, vec![(1,0),(1,1),(2,1),(3,1),(4,1),(5,1),(5,2),(5,3),(5,4),(6,4),(6,5),(6,6),(6,7),(6,8)])) 
@@ -409,7 +511,7 @@ mod tests {
                 solv.set_passage(c.0 as DimType, c.1 as DimType);
             }
         }
-        assert_eq!(solv.is_valid(), true);
+        assert_eq!(solv.is_valid().is_ok(), true);
         assert_eq!(solv.solve(false), Some((4, VecDeque::new())) );
     }
 
@@ -433,7 +535,7 @@ mod tests {
                 solv.set_passage(c.0 as DimType, c.1 as DimType);
             }
         }
-        assert_eq!(solv.is_valid(), true);
+        assert_eq!(solv.is_valid().is_ok(), true);
         assert_eq!(solv.solve(false), None );
     }
     
@@ -509,4 +611,38 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn torus() {
+        // Going left from the start and re-entering at the right edge needs only one turn
+        let arr = array![
+            [0,0,0,0,0,0u8],
+            [1,1,1,0,0,1u8],
+            [0,0,1,1,1,1u8],
+            [0,0,0,0,0,0u8],
+        ];
+        let adim = arr.shape();
+        let mut solv = Solver1::new_rc(adim[0] as DimType, adim[1] as DimType).unwrap();
+        for (c, v) in arr.indexed_iter() {
+            if *v == 1 {
+                solv.set_passage(c.0 as DimType, c.1 as DimType);
+            }
+        }
+        assert_eq!(solv.solve(false).map(|s| s.0), Some(2));
+        solv.set_topology(Topology::Toroidal);
+        assert_eq!(solv.solve(true), Some((1, vec![(1, 0), (1, 5), (2, 5)].into_iter().collect())));
+
+        // Moving straight across the seam is not a turn
+        let mut solv = Solver1::new_with_endpoints(4, 6, (1, 4), (1, 1)).unwrap();
+        for c in [0, 1, 4, 5].iter() {
+            solv.set_passage(1, *c);
+        }
+        assert_eq!(solv.solve(false), None);
+        solv.set_topology(Topology::Toroidal);
+        assert_eq!(solv.solve(true), Some((0, vec![(1, 4), (1, 5), (1, 0), (1, 1)].into_iter().collect())));
+        assert_eq!(solv.solve_multi(&[((1, 4), Some(Heading::Up))], &[(1, 1)], false).map(|s| s.rank), Some(1));
+        // Moving back across the seam is a turn
+        assert_eq!(solv.solve_multi(&[((1, 0), Some(Heading::Right))], &[(1, 5)], false).map(|s| s.rank), Some(1));
+        assert_eq!(solv.solve_multi(&[((1, 0), Some(Heading::Left))], &[(1, 5)], false).map(|s| s.rank), Some(0));
+    }
 }
@@ -1,35 +1,279 @@
-use std::collections::{HashMap, VecDeque};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
 
 use super::*;
 
 /// To store number of turns
 type RankType = DimType;
 
+/// Value of `data` for one-way cell is this plus code of its heading (`Heading as u8`), 0 is wall and 1 passage
+const ONE_WAY: u8 = 2;
+
+/// The cheapest way found by `Solver1::solve_penalized`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PenalizedSolution {
+    /// Number of turns plus weighted sum of penalties
+    pub cost: u64,
+    /// Number of turns
+    pub turns: u64,
+    /// Sum of penalties of the cells entered along the way
+    pub penalty: u64,
+    /// Nodes of the way - empty if not requested
+    pub path: VecDeque<NodeAdr>,
+}
+
 pub struct Solver1 {
     data: Vec<u8>,
     r_dim: DimType,
     c_dim: DimType,
     start: NodeAdr,
     end: NodeAdr,
+    topology: Topology,
+    /// Node => nodes linked with it by portals
+    portals: HashMap<NodeAdr, Vec<NodeAdr>>,
+    portal_cost: RankType,
+    /// Cell-cost layer, empty until the first `set_cell_cost` (all cells free)
+    cell_costs: Vec<CellCost>,
 }
 
 impl Solver1 {
 
-    pub fn new_rc(r: DimType, c:DimType) -> Result<Self, &'static str> {
-        if r < 2 || c < 2 { return Err("Too small dimensions"); }
+    pub fn new_rc(r: DimType, c:DimType) -> Result<Self, Error> {
+        let (start, end) = default_endpoints(r, c);
+        Self::new_with_endpoints(r, c, start, end)
+    }
+
+    /// Creates solver for maze with given start and end points
+    pub fn new_with_endpoints(r: DimType, c:DimType, start: NodeAdr, end: NodeAdr) -> Result<Self, Error> {
+        if r < 2 || c < 2 { return Err(Error::TooSmallDimensions { rows:r, cols:c }); }
         // Rough estimation of maximal number of turns 
         // TODO: figure out more exact condition (fun math exercise ;))
         // If this is too taught, set RankType to usize
-        if (r as usize + c as usize) / 2 > RankType::MAX as usize { return Err("Too big dimensions"); } 
+        if (r as usize + c as usize) / 2 > RankType::MAX as usize { return Err(Error::TooBigDimensions { rows:r, cols:c }); } 
+        check_endpoints(r, c, start, end)?;
         Ok(Solver1 { 
             data: vec![0; r as usize * c as usize], 
             r_dim:r, 
             c_dim:c,
-            start: (1,0),
-            end: (r - 2, c - 1),
+            start,
+            end,
+            topology: Topology::default(),
+            portals: HashMap::new(),
+            portal_cost: 0,
+            cell_costs: Vec::new(),
         })
     }
 
+    /// Sets shape of the maze borders used by `solve` and `solve_multi` and `k_best` (by default bounded).
+    /// Turns are counted the same way across the seam of toroidal maze, e.g. moving right from the last column
+    /// to the first one continues straight.
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    /// Makes the node one-way passage, which may be left only with given heading (but entered from any direction).
+    /// It is honoured by `solve`, `solve_multi` and `k_best`.
+    pub fn set_one_way(&mut self, r: DimType, c: DimType, heading: Heading) {
+        let idx = self.idx(r, c);
+        self.data[idx] = ONE_WAY + heading as u8;
+    }
+
+    /// Links two nodes with portal used by `solve` and `solve_multi`: stepping onto one of them allows to move
+    /// to the other one, at the cost set by `set_portal_cost`. The move from the portal exit in any direction is not a turn.
+    /// Portal is used only if both nodes are passages.
+    pub fn add_portal(&mut self, a: NodeAdr, b: NodeAdr) -> Result<(), Error> {
+        check_endpoints(self.r_dim, self.c_dim, a, b)?;
+        self.portals.entry(a).or_default().push(b);
+        self.portals.entry(b).or_default().push(a);
+        Ok(())
+    }
+
+    /// Sets number of turns which the move thru portal counts as (by default 0)
+    pub fn set_portal_cost(&mut self, turns: DimType) {
+        self.portal_cost = turns;
+    }
+
+    /// Sets cost of entering the node. Forbidden nodes are treated as walls by all solves
+    /// (until their cost is set back to other one), while penalties are used only by `solve_penalized`.
+    pub fn set_cell_cost(&mut self, r: DimType, c: DimType, cost: CellCost) {
+        if self.cell_costs.is_empty() {
+            if cost == CellCost::Free { return; }
+            self.cell_costs = vec![CellCost::Free; self.data.len()];
+        }
+        let idx = self.idx(r, c);
+        self.cell_costs[idx] = cost;
+    }
+
+    /// Penalty for entering given node
+    #[inline]
+    fn penalty(&self, (r, c): NodeAdr) -> u64 {
+        match self.cell_costs.get(self.idx(r, c)) {
+            Some(CellCost::Penalty(penalty)) => *penalty as u64,
+            _ => 0,
+        }
+    }
+
+    /// Finds the way with the lowest cost, which is number of turns plus `penalty_weight` times the sum of penalties
+    /// of the cells it enters (start cell is not entered, so its penalty does not count).
+    ///
+    /// Costs are arbitrary, so search runs on (node, heading) states with binary heap, like `weighted::solve_weighted`.
+    /// Honours topology and one-way cells. Moving back to the previous node is a turn here.
+    /// Portals are not supported - returns error if any was added.
+    pub fn solve_penalized(&self, penalty_weight: u64, with_path: bool) -> Result<Option<PenalizedSolution>, Error> {
+        if !self.portals.is_empty() {
+            return Err(Error::InvalidArgument("Portals are not supported by solve_penalized".to_string()));
+        }
+        if self.is_valid().is_err() { return Ok(None); }
+        // State = node index * 4 + heading
+        let len = self.data.len() * 4;
+        let mut cost = vec![u64::MAX; len];
+        let mut prev = vec![usize::MAX; len];
+        let mut todo = BinaryHeap::new();
+        // First move from start node in any direction is not a turn
+        for heading in 0..4 {
+            let state = self.idx(self.start.0, self.start.1) * 4 + heading;
+            cost[state] = 0;
+            prev[state] = state;
+            todo.push(Reverse((0, state)));
+        }
+        let mut end_state = None;
+        while let Some(Reverse((state_cost, state))) = todo.pop() {
+            if cost[state] < state_cost { continue; }
+            let node = self.rc(state / 4);
+            if node == self.end {
+                end_state = Some(state);
+                break;
+            }
+            for heading in Heading::ALL.iter() {
+                let next_node = match self.neighbor(node, *heading) {
+                    Some(next_node) => next_node,
+                    None => continue,
+                };
+                let next = self.idx(next_node.0, next_node.1) * 4 + *heading as usize;
+                let turn = if *heading as usize == state % 4 { 0 } else { 1 };
+                let next_cost = state_cost.saturating_add(turn).saturating_add(penalty_weight.saturating_mul(self.penalty(next_node)));
+                if next_cost < cost[next] {
+                    cost[next] = next_cost;
+                    prev[next] = state;
+                    todo.push(Reverse((next_cost, next)));
+                }
+            }
+        }
+
+        let end_state = match end_state {
+            Some(end_state) => end_state,
+            None => return Ok(None),
+        };
+        let mut turns = 0u64;
+        let mut penalty = 0;
+        let mut path = VecDeque::new();
+        let mut state = end_state;
+        loop {
+            let node = self.rc(state / 4);
+            if with_path { path.push_front(node); }
+            if prev[state] == state { break; }
+            if prev[state] % 4 != state % 4 { turns += 1; }
+            penalty += self.penalty(node);
+            state = prev[state];
+        }
+        Ok(Some(PenalizedSolution { cost: cost[end_state], turns, penalty, path }))
+    }
+
+    /// Nodes linked by portals with given node which are passages
+    fn portal_exits(&self, node: NodeAdr) -> impl Iterator<Item = NodeAdr> + '_ {
+        self.portals.get(&node).into_iter().flatten().copied().filter(move |n| self.is_passage(n.0, n.1))
+    }
+
+    /// Finds up to `k` simplest distinct simple ways from start to end (Yen's algorithm), ordered by number of turns
+    /// (and then by length). Returns empty vector if there is no way, or start or end point is not a passage.
+    pub fn k_best(&self, k: usize) -> Vec<(DimType, VecDeque<NodeAdr>)> {
+        let mut best = Vec::<(RankType, VecDeque<NodeAdr>)>::new();
+        if k == 0 || self.is_valid().is_err() { return best; }
+        match self.spur_path(self.start, None, &HashSet::new(), &HashSet::new()) {
+            Some(path) => best.push((self.path_rank(&path), path)),
+            None => return best,
+        }
+        let mut candidates = Vec::<(RankType, VecDeque<NodeAdr>)>::new();
+        while best.len() < k {
+            let last = &best[best.len() - 1].1;
+            for i in 0..last.len() - 1 {
+                // Way is branched off the last found one at spur node, following the same root path up to it
+                let spur = last[i];
+                let root: Vec<NodeAdr> = last.iter().take(i + 1).copied().collect();
+                // Root nodes, including the spur node itself, may not be entered again, so the way is simple
+                let blocked: HashSet<NodeAdr> = root.iter().copied().collect();
+                let forbidden: HashSet<NodeAdr> = best.iter()
+                    .filter(|(_, path)| path.len() > i + 1 && path.iter().take(i + 1).eq(root.iter()))
+                    .map(|(_, path)| path[i + 1])
+                    .collect();
+                let heading = if i > 0 { Some(self.move_heading(root[i - 1], spur)) } else { None };
+                if let Some(spur_path) = self.spur_path(spur, heading, &blocked, &forbidden) {
+                    let mut path: VecDeque<NodeAdr> = root.iter().take(i).copied().collect();
+                    path.extend(spur_path);
+                    if candidates.iter().chain(best.iter()).any(|(_, p)| *p == path) { continue; }
+                    candidates.push((self.path_rank(&path), path));
+                }
+            }
+            // Take the simplest candidate, ties are resolved by length and then by nodes, so that result is deterministic
+            let min_pos = match (0..candidates.len()).min_by(|a, b| {
+                let (rank_a, path_a) = &candidates[*a];
+                let (rank_b, path_b) = &candidates[*b];
+                (rank_a, path_a.len(), path_a).cmp(&(rank_b, path_b.len(), path_b))
+            }) {
+                Some(min_pos) => min_pos,
+                None => break,
+            };
+            best.push(candidates.swap_remove(min_pos));
+        }
+        best
+    }
+
+    /// Finds way from `spur` (entered with `heading`) to the end with minimal number of turns,
+    /// which does not enter `blocked` nodes and does not go from `spur` directly to `forbidden` nodes.
+    /// Search runs on (node, heading) states, as ties of nodes entered from different directions matter here.
+    /// All moves cost 0 or 1, so 0-1 BFS is used: states reached without turn go to the front of the deque.
+    fn spur_path(&self, spur: NodeAdr, heading: Option<Heading>, blocked: &HashSet<NodeAdr>, forbidden: &HashSet<NodeAdr>)
+        -> Option<VecDeque<NodeAdr>>
+    {
+        // (node, heading) => (rank, previous state)
+        let mut states = HashMap::<(NodeAdr, Heading), (RankType, Option<(NodeAdr, Heading)>)>::new();
+        let mut todo = VecDeque::new();
+        let headings = match heading {
+            Some(heading) => vec![heading],
+            None => Heading::ALL.to_vec(),
+        };
+        for heading in headings {
+            states.insert((spur, heading), (0, None));
+            todo.push_back(((spur, heading), 0));
+        }
+        while let Some((state, rank)) = todo.pop_front() {
+            if states[&state].0 < rank { continue; }
+            if state.0 == self.end {
+                let mut path = VecDeque::new();
+                let mut state = Some(state);
+                while let Some(cur_state) = state {
+                    path.push_front(cur_state.0);
+                    state = states[&cur_state].1;
+                }
+                return Some(path);
+            }
+            for node in self.neighbors(state.0) {
+                if blocked.contains(&node) || (state.0 == spur && forbidden.contains(&node)) { continue; }
+                let next = (node, self.move_heading(state.0, node));
+                let next_rank = if next.1 == state.1 { rank } else { rank + 1 };
+                let better = match states.get(&next) {
+                    Some((old_rank, _)) => next_rank < *old_rank,
+                    None => true,
+                };
+                if better {
+                    states.insert(next, (next_rank, Some(state)));
+                    if next_rank == rank { todo.push_front((next, next_rank)); } else { todo.push_back((next, next_rank)); }
+                }
+            }
+        }
+        None
+    }
+
     #[inline]
     fn idx(&self, r: DimType, c:DimType) -> usize {
         //if r >= self.r_dim { panic!("Wrong r argument") }
@@ -48,7 +292,17 @@ impl Solver1 {
 
     #[inline]
     fn is_passage(&self, r: DimType, c:DimType) -> bool {
-        self.data[self.idx(r, c)] == 1
+        let idx = self.idx(r, c);
+        self.data[idx] != 0 && self.cell_costs.get(idx) != Some(&CellCost::Forbidden)
+    }
+
+    /// Heading in which given node may be left, if it is one-way cell
+    #[inline]
+    fn one_way(&self, (r, c): NodeAdr) -> Option<Heading> {
+        match self.data[self.idx(r, c)] {
+            v if v >= ONE_WAY => Some(Heading::ALL[(v - ONE_WAY) as usize]),
+            _ => None,
+        }
     }
 
     #[inline]
@@ -56,6 +310,52 @@ impl Solver1 {
         NodeIter { solver:&self, r:rc.0, c:rc.1, mode:0 }
     }
 
+
+    /// Returns neighbor node in given direction if it is a passage (and the move is allowed by one-way cell)
+    #[inline]
+    fn neighbor(&self, (r, c): NodeAdr, heading: Heading) -> Option<NodeAdr> {
+        match self.one_way((r, c)) {
+            Some(one_way) if one_way != heading => return None,
+            _ => {},
+        }
+        let wrap = self.topology == Topology::Toroidal;
+        let node = match heading {
+            Heading::Up if r > 0 => (r - 1, c),
+            Heading::Up if wrap => (self.r_dim - 1, c),
+            Heading::Right if c < self.c_dim - 1 => (r, c + 1),
+            Heading::Right if wrap => (r, 0),
+            Heading::Down if r < self.r_dim - 1 => (r + 1, c),
+            Heading::Down if wrap => (0, c),
+            Heading::Left if c > 0 => (r, c - 1),
+            Heading::Left if wrap => (r, self.c_dim - 1),
+            _ => return None,
+        };
+        if self.is_passage(node.0, node.1) { Some(node) } else { None }
+    }
+
+    /// Heading of the move between neighbor nodes (also across the seam of toroidal maze)
+    fn move_heading(&self, from: NodeAdr, to: NodeAdr) -> Heading {
+        match (to.0 as i32 - from.0 as i32, to.1 as i32 - from.1 as i32) {
+            (-1, _) => Heading::Up,
+            (_, 1) => Heading::Right,
+            (1, _) => Heading::Down,
+            (_, -1) => Heading::Left,
+            // From the first row to the last one, etc.
+            (dr, _) if dr > 0 => Heading::Up,
+            (dr, _) if dr < 0 => Heading::Down,
+            (_, dc) if dc > 0 => Heading::Left,
+            _ => Heading::Right,
+        }
+    }
+
+    /// Number of turns along the way
+    fn path_rank(&self, path: &VecDeque<NodeAdr>) -> RankType {
+        let mut rank = 0;
+        for i in 2..path.len() {
+            if self.move_heading(path[i - 2], path[i - 1]) != self.move_heading(path[i - 1], path[i]) { rank += 1; }
+        }
+        rank
+    }
 }
 
 struct NodeIter<'a> {
@@ -72,26 +372,26 @@ impl Iterator for NodeIter<'_> {
             match self.mode {
                 0 => {
                     self.mode = 1;
-                    if self.r > 0 && self.solver.is_passage(self.r - 1, self.c) {
-                        return Some((self.r - 1, self.c));
+                    if let Some(node) = self.solver.neighbor((self.r, self.c), Heading::Up) {
+                        return Some(node);
                     }
                 },
                 1 => {
                     self.mode = 2;
-                    if self.c < self.solver.c_dim - 1 && self.solver.is_passage(self.r, self.c + 1) {
-                        return Some((self.r, self.c + 1));
+                    if let Some(node) = self.solver.neighbor((self.r, self.c), Heading::Right) {
+                        return Some(node);
                     }
                 },
                 2 => {
                     self.mode = 3;
-                    if self.r < self.solver.r_dim - 1 && self.solver.is_passage(self.r + 1, self.c) {
-                        return Some((self.r + 1, self.c));
+                    if let Some(node) = self.solver.neighbor((self.r, self.c), Heading::Down) {
+                        return Some(node);
                     }
                 },
                 3 => {
                     self.mode = 4;
-                    if self.c > 0 && self.solver.is_passage(self.r, self.c - 1) {
-                        return Some((self.r, self.c - 1));
+                    if let Some(node) = self.solver.neighbor((self.r, self.c), Heading::Left) {
+                        return Some(node);
                     }
                 },
                 _ => { return None; }
@@ -107,121 +407,138 @@ impl Solver for Solver1 {
         self.data[idx] = 1;
     }
 
-    fn is_valid(&self) -> bool {
-        self.data[self.idx(self.start.0, self.start.1)] == 1 
-        &&
-        self.data[self.idx(self.end.0, self.end.1)] == 1
+    fn is_valid(&self) -> Result<(), Error> {
+        if !self.is_passage(self.start.0, self.start.1) { return Err(Error::StartBlocked(self.start)); }
+        if !self.is_passage(self.end.0, self.end.1) { return Err(Error::EndBlocked(self.end)); }
+        Ok(())
     }
 
     fn solve(&self, with_path: bool) -> Option<(DimType, VecDeque<NodeAdr>)> {
+        if self.is_valid().is_err() { return None; }
+        self.solve_multi(&[(self.start, None)], &[self.end], with_path)
+            .map(|solution| (solution.rank, solution.path))
+    }
+
+    fn solve_multi(&self, starts: &[StartPoint], ends: &[NodeAdr], with_path: bool) -> Option<MultiSolution> {
         #[derive(Clone)]
         struct Nodedata {
             rank: RankType,
             prev: NodeAdr, 
+            // entered thru portal - the next move in any direction is not a turn
+            portal: bool,
         }
         
-        if !self.is_valid() { return None; }
+        let is_valid_node = |node: &NodeAdr| node.0 < self.r_dim && node.1 < self.c_dim && self.is_passage(node.0, node.1);
+        let mut start_idx = HashMap::<NodeAdr, usize>::new();
+        let mut end_idx = HashMap::<NodeAdr, usize>::new();
+        for (idx, node) in ends.iter().enumerate().filter(|(_, node)| is_valid_node(node)) {
+            end_idx.entry(*node).or_insert(idx);
+        }
 
         // with_capacity() here degrades performance
         let mut purgatory = HashMap::<NodeAdr, Nodedata>::new(); // (r,c) => Nodedata
         let mut heaven = HashMap::<NodeAdr, Nodedata>::new(); // (r,c) => Nodedata
-        let mut cur_node = self.start;
-        let mut cur_node_data = Nodedata {rank:0, prev:cur_node};
-        heaven.insert(cur_node, cur_node_data.clone());
+        for (idx, (node, heading)) in starts.iter().enumerate().filter(|(_, (node, _))| is_valid_node(node)) {
+            if start_idx.contains_key(node) { continue; }
+            start_idx.insert(*node, idx);
+            purgatory.insert(*node, Nodedata {rank:0, prev:start_prev(*node, *heading, self.r_dim, self.c_dim, self.topology), portal:false});
+        }
+        let mut end_node = None;
         loop {
+            // Find todo node with minimal rank
+            // TODO: This loop probably could be improved:
+            // - maybe use some sorted containers
+            // - maybe parallelize
+            let mut min_val = RankType::MAX;
+            let mut min_node = None;
+            for (node, node_data) in purgatory.iter() {
+                if node_data.rank < min_val {
+                    min_val = node_data.rank;
+                    min_node = Some(*node);
+                }
+            }
+
+            // If list of non fixed nodes is empty or we arrived into end node - exit loop
+            let cur_node = match min_node {
+                Some(min_node) => min_node,
+                None => break,
+            };
+            let cur_node_data = purgatory.remove(&cur_node).expect("Logical error - min_node should be in the purgatory");
+            heaven.insert(cur_node, cur_node_data.clone());
+            if end_idx.contains_key(&cur_node) {
+                end_node = Some(cur_node);
+                break;
+            }
+
             // Evaluate rate of neighbor nodes 
             for node in self.neighbors(cur_node) {
-                if let Some(mut node_data) = purgatory.get_mut(&node) {
-                    let (r, c) = cur_node_data.prev;
-                    if node.0 == cur_node.0 && cur_node.0 == r {
-                        // continue moving in vertical direction
+                if let Some(node_data) = purgatory.get_mut(&node) {
+                    if cur_node_data.portal || !is_turn(cur_node_data.prev, cur_node, node) {
+                        // continue moving in vertical or horizontal direction
                         if node_data.rank > cur_node_data.rank {
-                            node_data.rank = cur_node_data.rank;
-                            node_data.prev = cur_node;
-                        }
-                    }
-                    else if node.1 == cur_node.1 && cur_node.1 == c {
-                        // continue moving in horizontal direction
-                        if node_data.rank > cur_node_data.rank {
-                            node_data.rank = cur_node_data.rank;
-                            node_data.prev = cur_node;
+                            *node_data = Nodedata {rank:cur_node_data.rank, prev:cur_node, portal:false};
                         }
                     } else {
                         // turn
                         if node_data.rank > cur_node_data.rank + 1 {
-                            node_data.rank = cur_node_data.rank + 1;
-                            node_data.prev = cur_node;
+                            *node_data = Nodedata {rank:cur_node_data.rank + 1, prev:cur_node, portal:false};
                         }
                     }
                 }
                 else if !heaven.contains_key(&node) {
-                    let (r, c) = cur_node_data.prev;
                     let init_rank = 
-                        if node.0 == cur_node.0 && cur_node.0 == r {
-                            // continue moving in vertical direction
-                            cur_node_data.rank
-                        }
-                        else if node.1 == cur_node.1 && cur_node.1 == c {
-                            // continue moving in horizontal direction
+                        if cur_node_data.portal || !is_turn(cur_node_data.prev, cur_node, node) {
+                            // continue moving in vertical or horizontal direction
                             cur_node_data.rank
                         } else {
                             // turn
                             cur_node_data.rank + 1
                         };
-                    purgatory.insert(node, Nodedata {rank:init_rank, prev:cur_node});
+                    purgatory.insert(node, Nodedata {rank:init_rank, prev:cur_node, portal:false});
                 }
             }
 
-            // Find todo node with minimal rank
-            // TODO: This loop probably could be improved:
-            // - maybe use some sorted containers
-            // - maybe parallelize
-            let mut min_val = RankType::MAX;
-            let mut min_node = None;
-            for (node, node_data) in purgatory.iter() {
-                if node_data.rank < min_val {
-                    min_val = node_data.rank;
-                    min_node = Some(*node);
+            // Move thru portal
+            for node in self.portal_exits(cur_node) {
+                let rank = cur_node_data.rank.saturating_add(self.portal_cost);
+                if let Some(node_data) = purgatory.get_mut(&node) {
+                    if node_data.rank > rank {
+                        *node_data = Nodedata {rank, prev:cur_node, portal:true};
+                    }
                 }
-            }
-
-            // If list of non fixed nodes is empty or we arrived into end node - exit loop
-            if let Some(min_node) = min_node {
-                let min_node_data = purgatory.remove(&min_node).expect("Logical error - min_node should be in the purgatory");
-                cur_node = min_node;
-                cur_node_data = min_node_data.clone();
-                heaven.insert(min_node, min_node_data);
-                if min_node == self.end {
-                    break;
+                else if !heaven.contains_key(&node) {
+                    purgatory.insert(node, Nodedata {rank, prev:cur_node, portal:true});
                 }
-            } else {
-                break;
             }
         }
 
-        if let Some(end_node_data) = heaven.get(&self.end) {
-            let mut path = VecDeque::new();
+        let end_node = end_node?;
+        let mut path = VecDeque::new();
+        // Path is followed also without with_path, to find which start it begins with.
+        // Start nodes have rank 0 from the beginning, so they are never reached from other nodes.
+        let mut node = end_node;
+        let mut path_len = 0;
+        loop {
             if with_path {
-                let mut node = self.end;
-                loop {
-                    path.push_front(node);
-                    let node_data = heaven.get(&node).expect("Logical error - every node on path should be in heaven");
-                    if node == self.start { break; }
-                    node = node_data.prev;
-                    if path.len() > heaven.len() {
-                        panic!("Something wrong - Cycle detected");
-                        // eprint!("Something wrong - Cycle detected");
-                        // break;
-                    }
-                }
+                path.push_front(node);
+            }
+            if start_idx.contains_key(&node) { break; }
+            node = heaven.get(&node).expect("Logical error - every node on path should be in heaven").prev;
+            path_len += 1;
+            if path_len > heaven.len() {
+                panic!("Something wrong - Cycle detected");
             }
-            Some((end_node_data.rank, path))
-        } else {
-            None
         }
+        Some(MultiSolution {
+            rank: heaven[&end_node].rank,
+            start: start_idx[&node],
+            end: end_idx[&end_node],
+            path,
+        })
     }
 }
 
+
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 /// Tests
 /// 
@@ -234,7 +551,7 @@ mod tests {
     use ndarray_rand::RandomExt;
     use ndarray_rand::rand_distr::Uniform;
     use super::*;
-    use crate::tests::init_from_rand_array;
+    use crate::tests::{init_from_rand_array, rand_maze, reference_rank, count_turns};
 
     #[test]
     fn coordinates_conversion() {
@@ -296,7 +613,7 @@ mod tests {
                 solv.set_passage(c.0 as DimType, c.1 as DimType);
             }
         }
-        assert_eq!(solv.is_valid(), true);
+        assert_eq!(solv.is_valid().is_ok(), true);
         assert_eq!(
             format!("{:?}", solv.solve(true).unwrap()), 
             format!("{:?}", (4, vec![(1,0),(1,1),(2,1),(3,1),(4,1),(5,1),(5,2),(5,3),(5,4),(6,4),(6,5),(6,6),(6,7),(6,8)])) 
@@ -323,7 +640,7 @@ mod tests {
                 solv.set_passage(c.0 as DimType, c.1 as DimType);
             }
         }
-        assert_eq!(solv.is_valid(), true);
+        assert_eq!(solv.is_valid().is_ok(), true);
         assert_eq!(solv.solve(false), Some((4, VecDeque::new())) );
     }
 
@@ -347,7 +664,7 @@ mod tests {
                 solv.set_passage(c.0 as DimType, c.1 as DimType);
             }
         }
-        assert_eq!(solv.is_valid(), true);
+        assert_eq!(solv.is_valid().is_ok(), true);
         assert_eq!(solv.solve(false), None );
     }
     
@@ -412,4 +729,238 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn k_best() {
+        let arr = array![
+            [0,0,0,0,0,0u8],
+            [1,1,1,1,1,0u8],
+            [0,1,0,0,1,0u8],
+            [0,1,1,1,1,0u8],
+            [0,0,0,0,1,1u8],
+            [0,0,0,0,0,0u8],
+        ];
+        let adim = arr.shape();
+        let mut solv = Solver1::new_rc(adim[0] as DimType, adim[1] as DimType).unwrap();
+        for (c, v) in arr.indexed_iter() {
+            if *v == 1 {
+                solv.set_passage(c.0 as DimType, c.1 as DimType);
+            }
+        }
+        let paths = solv.k_best(5);
+        assert_eq!(paths.iter().map(|(rank, path)| (*rank, path.iter().copied().collect::<Vec<_>>())).collect::<Vec<_>>(), vec![
+            (2, vec![(1,0),(1,1),(1,2),(1,3),(1,4),(2,4),(3,4),(4,4),(4,5)]),
+            (4, vec![(1,0),(1,1),(2,1),(3,1),(3,2),(3,3),(3,4),(4,4),(4,5)]),
+        ]);
+        assert_eq!(solv.k_best(1).len(), 1);
+        assert!(solv.k_best(0).is_empty());
+        assert!(Solver1::new_rc(4, 4).unwrap().k_best(3).is_empty());
+    }
+
+    /// Collects numbers of turns of all simple ways from last node of `path` to the end
+    fn all_ranks(solv: &Solver1, path: &mut VecDeque<NodeAdr>, ranks: &mut Vec<DimType>) {
+        let node = *path.back().unwrap();
+        if node == solv.end {
+            ranks.push(solv.path_rank(path));
+            return;
+        }
+        for next in solv.neighbors(node) {
+            if path.contains(&next) { continue; }
+            path.push_back(next);
+            all_ranks(solv, path, ranks);
+            path.pop_back();
+        }
+    }
+
+    #[test]
+    fn k_best_brute_force() {
+        let mut rng = rand::thread_rng();
+        let r_dim = rng.gen_range(4, 6);
+        let c_dim = rng.gen_range(4, 7);
+        let arr = Array::random((r_dim, c_dim), Uniform::new_inclusive(0, 100));
+        let mut solv = Solver1::new_rc(r_dim as DimType, c_dim as DimType).unwrap();
+        init_from_rand_array(&mut solv, &arr.view(), 70);
+        let mut ranks = Vec::new();
+        all_ranks(&solv, &mut vec![solv.start].into_iter().collect(), &mut ranks);
+        ranks.sort();
+        ranks.truncate(8);
+        let paths = solv.k_best(8);
+        assert_eq!(paths.iter().map(|(rank, _)| *rank).collect::<Vec<_>>(), ranks);
+        for (i, (rank, path)) in paths.iter().enumerate() {
+            assert_eq!(count_turns(path), *rank);
+            assert_eq!(path.front(), Some(&solv.start));
+            assert_eq!(path.back(), Some(&solv.end));
+            assert!(path.iter().zip(path.iter().skip(1)).all(|(a, b)| solv.neighbors(*a).any(|n| n == *b)));
+            assert!(paths.iter().take(i).all(|(_, p)| p != path));
+            assert_eq!(path.iter().collect::<HashSet<_>>().len(), path.len());
+        }
+    }
+
+    #[test]
+    fn k_best_ties() {
+        // Open area, where many ways have the same number of turns
+        let mut solv = Solver1::new_rc(5, 5).unwrap();
+        for r in 1..4 {
+            for c in 0..5 {
+                solv.set_passage(r, c);
+            }
+        }
+        let mut ranks = Vec::new();
+        all_ranks(&solv, &mut vec![solv.start].into_iter().collect(), &mut ranks);
+        ranks.sort();
+        ranks.truncate(40);
+        let paths = solv.k_best(40);
+        assert_eq!(paths.iter().map(|(rank, _)| *rank).collect::<Vec<_>>(), ranks);
+        for (i, (rank, path)) in paths.iter().enumerate() {
+            assert_eq!(count_turns(path), *rank);
+            assert!(paths.iter().take(i).all(|(_, p)| p != path));
+            assert_eq!(path.iter().collect::<HashSet<_>>().len(), path.len());
+        }
+    }
+
+    #[test]
+    fn torus() {
+        // Going left from the start and re-entering at the right edge needs only one turn
+        let arr = array![
+            [0,0,0,0,0,0u8],
+            [1,1,1,0,0,1u8],
+            [0,0,1,1,1,1u8],
+            [0,0,0,0,0,0u8],
+        ];
+        let adim = arr.shape();
+        let mut solv = Solver1::new_rc(adim[0] as DimType, adim[1] as DimType).unwrap();
+        for (c, v) in arr.indexed_iter() {
+            if *v == 1 {
+                solv.set_passage(c.0 as DimType, c.1 as DimType);
+            }
+        }
+        assert_eq!(solv.solve(false).map(|s| s.0), Some(2));
+        solv.set_topology(Topology::Toroidal);
+        assert_eq!(solv.solve(true), Some((1, vec![(1, 0), (1, 5), (2, 5)].into_iter().collect())));
+
+        // Moving straight across the seam is not a turn
+        let mut solv = Solver1::new_with_endpoints(4, 6, (1, 4), (1, 1)).unwrap();
+        for c in [0, 1, 4, 5].iter() {
+            solv.set_passage(1, *c);
+        }
+        assert_eq!(solv.solve(false), None);
+        solv.set_topology(Topology::Toroidal);
+        assert_eq!(solv.solve(true), Some((0, vec![(1, 4), (1, 5), (1, 0), (1, 1)].into_iter().collect())));
+        assert_eq!(solv.solve_multi(&[((1, 4), Some(Heading::Up))], &[(1, 1)], false).map(|s| s.rank), Some(1));
+        // Moving back across the seam is a turn
+        assert_eq!(solv.solve_multi(&[((1, 0), Some(Heading::Right))], &[(1, 5)], false).map(|s| s.rank), Some(1));
+        assert_eq!(solv.solve_multi(&[((1, 0), Some(Heading::Left))], &[(1, 5)], false).map(|s| s.rank), Some(0));
+        assert_eq!(solv.k_best(2), vec![(0, vec![(1, 4), (1, 5), (1, 0), (1, 1)].into_iter().collect())]);
+    }
+
+    #[test]
+    fn cell_costs() {
+        // Corridor with one turn thru the mud at (1,3), and the detour below it with two turns
+        let arr = array![
+            [0,0,0,0,0,0,0u8],
+            [1,1,1,1,1,1,1u8],
+            [0,1,0,0,0,0,1u8],
+            [0,1,1,1,1,1,1u8],
+            [0,0,0,0,0,0,0u8],
+        ];
+        let mut solv = Solver1::new_with_endpoints(5, 7, (1, 0), (3, 6)).unwrap();
+        for (c, v) in arr.indexed_iter() {
+            if *v == 1 {
+                solv.set_passage(c.0 as DimType, c.1 as DimType);
+            }
+        }
+        let corridor: VecDeque<NodeAdr> = (0..7).map(|c| (1, c)).chain(vec![(2, 6), (3, 6)]).collect();
+        assert_eq!(
+            solv.solve_penalized(1, true).unwrap(),
+            Some(PenalizedSolution { cost:1, turns:1, penalty:0, path:corridor.clone() })
+        );
+        solv.set_cell_cost(1, 3, CellCost::Penalty(3));
+        assert_eq!(
+            solv.solve_penalized(0, true).unwrap(),
+            Some(PenalizedSolution { cost:1, turns:1, penalty:3, path:corridor })
+        );
+        assert_eq!(
+            solv.solve_penalized(1, true).unwrap(),
+            Some(PenalizedSolution { cost:2, turns:2, penalty:0, path:vec![(1, 0), (1, 1), (2, 1)].into_iter().chain((1..7).map(|c| (3, c))).collect() })
+        );
+        // Penalties do not matter for the other solves
+        assert_eq!(solv.solve(false).map(|s| s.0), Some(1));
+
+        // Forbidden cell is a wall for all solves, until it is freed
+        solv.set_cell_cost(1, 3, CellCost::Forbidden);
+        assert_eq!(solv.solve(false).map(|s| s.0), Some(2));
+        assert_eq!(solv.solve_penalized(0, false).unwrap().map(|s| s.turns), Some(2));
+        solv.set_cell_cost(1, 1, CellCost::Forbidden);
+        assert_eq!(solv.solve(false), None);
+        assert_eq!(solv.solve_penalized(1, false).unwrap(), None);
+        solv.set_cell_cost(1, 0, CellCost::Forbidden);
+        assert!(matches!(solv.is_valid(), Err(Error::StartBlocked((1, 0)))));
+        solv.set_cell_cost(1, 0, CellCost::Free);
+        solv.set_cell_cost(1, 1, CellCost::Free);
+        solv.set_cell_cost(1, 3, CellCost::Free);
+        assert_eq!(solv.solve_penalized(1, false).unwrap().map(|s| s.cost), Some(1));
+
+        // Portals are used by the other solves, so they are rejected rather than ignored
+        solv.add_portal((1, 0), (3, 6)).unwrap();
+        assert_eq!(solv.solve(false).map(|s| s.0), Some(0));
+        assert!(matches!(solv.solve_penalized(1, false), Err(Error::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn penalized_many_turns() {
+        // Free staircase bouncing between the top and bottom rows, all other cells are expensive,
+        // so the cheapest way makes more turns than `DimType` holds
+        let (r_dim, c_dim) = (10, 50000);
+        let mut stairs = HashSet::new();
+        let (mut r, mut c, mut down) = (0, 0, true);
+        stairs.insert((r, c));
+        while c < c_dim - 1 {
+            c += 1;
+            stairs.insert((r, c));
+            if r == 0 { down = true; } else if r == r_dim - 1 { down = false; }
+            if down { r += 1; } else { r -= 1; }
+            stairs.insert((r, c));
+        }
+        let mut solv = Solver1::new_with_endpoints(r_dim, c_dim, (0, 0), (r, c)).unwrap();
+        for r in 0..r_dim {
+            for c in 0..c_dim {
+                solv.set_passage(r, c);
+                if !stairs.contains(&(r, c)) { solv.set_cell_cost(r, c, CellCost::Penalty(9)); }
+            }
+        }
+        let solution = solv.solve_penalized(2, true).unwrap().unwrap();
+        assert!(solution.turns > DimType::MAX as u64);
+        assert_eq!((solution.cost, solution.penalty), (solution.turns, 0));
+        let path: Vec<_> = solution.path.into_iter().collect();
+        let heading = |a: &NodeAdr, b: &NodeAdr| (b.0 as isize - a.0 as isize, b.1 as isize - a.1 as isize);
+        assert_eq!(path.windows(3).filter(|w| heading(&w[0], &w[1]) != heading(&w[1], &w[2])).count() as u64, solution.turns);
+    }
+
+    #[test]
+    fn penalized_cross_check() {
+        // Without penalties the number of turns is the same as of bfs01 (which is exact, unlike solve in some ties),
+        // with them the number of turns is never lower
+        let mut rng = rand::thread_rng();
+        let maze = rand_maze(30, 75);
+        let reference = reference_rank(&maze);
+        let mut solv = Solver1::new_rc(maze.rows(), maze.cols()).unwrap();
+        maze.load_into(&mut solv);
+        let plain = solv.solve_penalized(1, true).unwrap();
+        assert_eq!(plain.as_ref().map(|s| (s.cost, s.turns, s.penalty)), reference.map(|rank| (rank as u64, rank as u64, 0)));
+        if let Some(PenalizedSolution { turns, path, .. }) = plain {
+            assert_eq!(count_turns(&path) as u64, turns);
+        }
+        for (r, c) in maze.passages() {
+            if rng.gen_range(0, 10) == 0 { solv.set_cell_cost(r, c, CellCost::Penalty(rng.gen_range(1, 11))); }
+        }
+        let weight = rng.gen_range(0, 4);
+        let solution = solv.solve_penalized(weight, true).unwrap();
+        assert_eq!(solution.is_some(), reference.is_some());
+        if let Some(PenalizedSolution { cost, turns, penalty, path }) = solution {
+            assert_eq!(cost, turns + weight * penalty);
+            assert_eq!(count_turns(&path) as u64, turns);
+            assert_eq!(path.iter().skip(1).map(|node| solv.penalty(*node)).sum::<u64>(), penalty);
+            assert!(reference.unwrap() as u64 <= turns);
+        }
+    }
 }
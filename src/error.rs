@@ -0,0 +1,115 @@
+use std::fmt;
+use std::io;
+
+use super::*;
+
+/// Errors of maze parsing and solver construction.
+/// Line and column numbers are counted from 1, as in the input file.
+#[derive(Debug)]
+pub enum Error {
+    /// Maze must have at least 2 rows and 2 columns
+    TooSmallDimensions { rows: DimType, cols: DimType },
+    /// Layered maze must have at least 1 level
+    TooFewLevels(DimType),
+    /// Maze is too big for the solver (number of turns or nodes would not fit in its types)
+    TooBigDimensions { rows: DimType, cols: DimType },
+    /// First line of input is not in `X,Y` format
+    InvalidHeader(String),
+    WrongLineLength { line: usize, expected: DimType, actual: usize },
+    InvalidCharacter { line: usize, column: usize, ch: char },
+    Io { line: usize, source: io::Error },
+    /// Line after the maze rows is not in `r1,c1,r2,c2` format, or the node lies outside of the maze
+    InvalidPortal { line: usize, text: String },
+    /// Start or end point does not lie within the maze
+    EndpointOutOfRange(NodeAdr),
+    /// Start point of the maze is not a passage
+    StartBlocked(NodeAdr),
+    /// End point of the maze is not a passage
+    EndBlocked(NodeAdr),
+    /// The same as `EndpointOutOfRange`, `StartBlocked` and `EndBlocked` for layered maze
+    EndpointOutOfRange3(NodeAdr3),
+    StartBlocked3(NodeAdr3),
+    EndBlocked3(NodeAdr3),
+    /// There are ways thru the maze, but none of them satisfies the limits of straight segments length
+    RunLimitsUnsatisfied,
+    /// Wrong command line or function argument
+    InvalidArgument(String),
+    UnknownSolver(String),
+}
+
+impl Error {
+    /// Exit code of the program for this error:
+    /// -1 if data are correct, but there is no result, -2 if data are incorrect,
+    /// -3 if there are ways, but none of them satisfies the limits of straight segments length
+    /// (as the exit status is taken modulo 256, on Unix those are seen as 255, 254 and 253)
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::StartBlocked(_) | Error::EndBlocked(_) | Error::StartBlocked3(_) | Error::EndBlocked3(_) => -1,
+            Error::RunLimitsUnsatisfied => -3,
+            _ => -2,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::TooSmallDimensions { rows, cols } => write!(f, "Too small dimensions: {} rows, {} columns", rows, cols),
+            Error::TooFewLevels(levels) => write!(f, "Too small dimensions: {} levels", levels),
+            Error::TooBigDimensions { rows, cols } => write!(f, "Too big dimensions: {} rows, {} columns", rows, cols),
+            Error::InvalidHeader(msg) => write!(f, "Input line 1: {}", msg),
+            Error::WrongLineLength { line, expected, actual } => write!(f, "Input line {}: line should have {} characters, but has {}", line, expected, actual),
+            Error::InvalidCharacter { line, column, ch } => write!(f, "Input line {}, column {}: invalid character: {}", line, column, ch),
+            Error::Io { line, source } => write!(f, "Input line {}: {}", line, source),
+            Error::InvalidPortal { line, text } => write!(f, "Input line {}: invalid portal: {}", line, text),
+            Error::EndpointOutOfRange(node) => write!(f, "Point {:?} is outside of the maze", node),
+            Error::StartBlocked(node) => write!(f, "Start point {:?} is not a passage", node),
+            Error::EndBlocked(node) => write!(f, "End point {:?} is not a passage", node),
+            Error::EndpointOutOfRange3(node) => write!(f, "Point {:?} is outside of the maze", node),
+            Error::StartBlocked3(node) => write!(f, "Start point {:?} is not a passage", node),
+            Error::EndBlocked3(node) => write!(f, "End point {:?} is not a passage", node),
+            Error::RunLimitsUnsatisfied => write!(f, "There are ways thru the maze, but none of them satisfies the limits of straight segments length"),
+            Error::InvalidArgument(msg) => write!(f, "{}", msg),
+            Error::UnknownSolver(name) => write!(f, "Unknown solver: \"{}\"", name),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_code() {
+        assert_eq!(Error::StartBlocked((1, 0)).exit_code(), -1);
+        assert_eq!(Error::EndBlocked((1, 0)).exit_code(), -1);
+        assert_eq!(Error::StartBlocked3((0, 1, 0)).exit_code(), -1);
+        assert_eq!(Error::EndpointOutOfRange3((0, 1, 0)).exit_code(), -2);
+        assert_eq!(Error::RunLimitsUnsatisfied.exit_code(), -3);
+        assert_eq!(Error::TooSmallDimensions { rows:1, cols:5 }.exit_code(), -2);
+        assert_eq!(dijkstra_speed::Solver1::new_rc(1, 5).err().unwrap().exit_code(), -2);
+    }
+
+    #[test]
+    fn source() {
+        use std::error::Error as _;
+        let e = Error::Io { line:2, source:io::Error::new(io::ErrorKind::UnexpectedEof, "eof") };
+        assert_eq!(e.to_string(), "Input line 2: eof");
+        assert!(e.source().is_some());
+        assert!(Error::EndBlocked((1, 0)).source().is_none());
+        assert_eq!(Error::UnknownSolver(String::new()).to_string(), "Unknown solver: \"\"");
+    }
+}
@@ -0,0 +1,72 @@
+//! Rectangular grid with start and end points, shared by solvers which run their own search
+//! on extended states (`breaking`, `keys`, `straight_run`).
+
+use super::*;
+
+/// Cells of the maze: 0 is a wall and any other value is a passage
+/// (solvers may use values other than 1 to mark special passages)
+pub(crate) struct Grid {
+    pub(crate) data: Vec<u8>,
+    pub(crate) r_dim: DimType,
+    pub(crate) c_dim: DimType,
+    pub(crate) start: NodeAdr,
+    pub(crate) end: NodeAdr,
+}
+
+impl Grid {
+
+    /// Creates grid filled with walls, with given start and end points
+    pub(crate) fn new_with_endpoints(r: DimType, c: DimType, start: NodeAdr, end: NodeAdr) -> Result<Self, Error> {
+        if r < 2 || c < 2 { return Err(Error::TooSmallDimensions { rows:r, cols:c }); }
+        check_endpoints(r, c, start, end)?;
+        Ok(Grid {
+            data: vec![0; r as usize * c as usize],
+            r_dim:r,
+            c_dim:c,
+            start,
+            end,
+        })
+    }
+
+    #[inline]
+    pub(crate) fn idx(&self, (r, c): NodeAdr) -> usize {
+        r as usize * self.c_dim as usize + c as usize
+    }
+
+    #[inline]
+    pub(crate) fn rc(&self, idx: usize) -> NodeAdr {
+        ((idx / self.c_dim as usize) as DimType, (idx % self.c_dim as usize) as DimType)
+    }
+
+    #[inline]
+    pub(crate) fn is_passage(&self, node: NodeAdr) -> bool {
+        self.data[self.idx(node)] != 0
+    }
+
+    /// Sets value of given cell (1 for ordinary passage)
+    pub(crate) fn set_cell(&mut self, r: DimType, c: DimType, value: u8) {
+        let idx = self.idx((r, c));
+        self.data[idx] = value;
+    }
+
+    /// Checks that both start and end points are passages
+    pub(crate) fn is_valid(&self) -> Result<(), Error> {
+        if !self.is_passage(self.start) { return Err(Error::StartBlocked(self.start)); }
+        if !self.is_passage(self.end) { return Err(Error::EndBlocked(self.end)); }
+        Ok(())
+    }
+
+    /// Returns index of the node next to given one in given direction, if it lies within the grid
+    #[inline]
+    pub(crate) fn step(&self, idx: usize, heading: Heading) -> Option<usize> {
+        let (r, c) = self.rc(idx);
+        let c_dim = self.c_dim as usize;
+        match heading {
+            Heading::Up if r > 0 => Some(idx - c_dim),
+            Heading::Right if c < self.c_dim - 1 => Some(idx + 1),
+            Heading::Down if r < self.r_dim - 1 => Some(idx + c_dim),
+            Heading::Left if c > 0 => Some(idx - 1),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,314 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+use super::*;
+
+/// To store number of turns
+type RankType = DimType;
+
+/// Number of move directions on hex grid. Directions are numbered clockwise:
+/// 0 - east, 1 - south-east, 2 - south-west, 3 - west, 4 - north-west, 5 - north-east.
+const DIRECTIONS: usize = 6;
+
+/// Directions of moves without turn for start point with given heading:
+/// moving up or down may start in either of two directions.
+fn start_directions(heading: Option<Heading>) -> &'static [usize] {
+    match heading {
+        None => &[0, 1, 2, 3, 4, 5],
+        Some(Heading::Right) => &[0],
+        Some(Heading::Down) => &[1, 2],
+        Some(Heading::Left) => &[3],
+        Some(Heading::Up) => &[4, 5],
+    }
+}
+
+/// Maze on hex grid in "odd-r" offset coordinates: nodes are addressed by (row, column) like on square grid,
+/// but odd rows are shifted right by half of the node. So every node has 6 neighbors: 2 in its row,
+/// and 2 in each of the rows above and below. Turn is any change of direction (by 60, 120 or 180 degrees).
+///
+/// The input format is the same as for square grid - `X,Y` header and rows of `0` and `1`,
+/// with rows interpreted as described above.
+pub struct Solver1 {
+    data: Vec<u8>,
+    r_dim: DimType,
+    c_dim: DimType,
+    start: NodeAdr,
+    end: NodeAdr,
+}
+
+/// Result of single `search()` run
+struct Found {
+    cost: u64,
+    /// Nodes of the way together with direction of the move into them (not defined for the start node)
+    path: VecDeque<(NodeAdr, usize)>,
+}
+
+impl Solver1 {
+
+    pub fn new_rc(r: DimType, c:DimType) -> Result<Self, Error> {
+        let (start, end) = default_endpoints(r, c);
+        Self::new_with_endpoints(r, c, start, end)
+    }
+
+    /// Creates solver for maze with given start and end points
+    pub fn new_with_endpoints(r: DimType, c:DimType, start: NodeAdr, end: NodeAdr) -> Result<Self, Error> {
+        if r < 2 || c < 2 { return Err(Error::TooSmallDimensions { rows:r, cols:c }); }
+        // Rough estimation of maximal number of turns
+        // If this is too taught, set RankType to usize
+        if (r as usize + c as usize) > RankType::MAX as usize { return Err(Error::TooBigDimensions { rows:r, cols:c }); }
+        check_endpoints(r, c, start, end)?;
+        Ok(Solver1 {
+            data: vec![0; r as usize * c as usize],
+            r_dim:r,
+            c_dim:c,
+            start,
+            end,
+        })
+    }
+
+    #[inline]
+    fn idx(&self, r: DimType, c:DimType) -> usize {
+        r as usize * self.c_dim as usize + c as usize
+    }
+
+    #[inline]
+    fn rc(&self, idx: usize) -> NodeAdr {
+        let r = idx / self.c_dim as usize;
+        let c = idx - r * self.c_dim as usize;
+        (r as DimType, c as DimType)
+    }
+
+    #[inline]
+    fn is_passage(&self, r: DimType, c:DimType) -> bool {
+        self.data[self.idx(r, c)] == 1
+    }
+
+    #[inline]
+    fn is_valid_node(&self, node: &NodeAdr) -> bool {
+        node.0 < self.r_dim && node.1 < self.c_dim && self.is_passage(node.0, node.1)
+    }
+
+    /// Returns neighbor node in given direction if it is a passage
+    fn neighbor(&self, (r, c): NodeAdr, direction: usize) -> Option<NodeAdr> {
+        // In odd rows neighbors in rows above and below are shifted right
+        let shift = (r % 2) as i32;
+        let (dr, dc) = match direction {
+            0 => (0, 1),
+            1 => (1, shift),
+            2 => (1, shift - 1),
+            3 => (0, -1),
+            4 => (-1, shift - 1),
+            _ => (-1, shift),
+        };
+        let (nr, nc) = (r as i32 + dr, c as i32 + dc);
+        if nr < 0 || nc < 0 || nr >= self.r_dim as i32 || nc >= self.c_dim as i32 { return None; }
+        let node = (nr as DimType, nc as DimType);
+        if self.is_passage(node.0, node.1) { Some(node) } else { None }
+    }
+
+    /// Dijkstra search on (node, direction) states from any of `starts` to any of `ends`.
+    /// Every move costs `step` plus `turn_cost` of the change of direction (given by clockwise difference 1..5).
+    fn search(&self, starts: &[StartPoint], ends: &[NodeAdr], step: u64, turn_cost: impl Fn(usize) -> u64) -> Option<Found> {
+        let mut is_end = vec![false; self.data.len()];
+        for node in ends.iter().filter(|node| self.is_valid_node(node)) {
+            is_end[self.idx(node.0, node.1)] = true;
+        }
+        // State = node index * 6 + direction
+        let mut cost = vec![u64::MAX; self.data.len() * DIRECTIONS];
+        let mut prev = vec![usize::MAX; self.data.len() * DIRECTIONS];
+        let mut todo = BinaryHeap::new();
+        for (node, heading) in starts.iter().filter(|(node, _)| self.is_valid_node(node)) {
+            let idx = self.idx(node.0, node.1);
+            // The first occurrence of the node is used
+            if (0..DIRECTIONS).any(|direction| prev[idx * DIRECTIONS + direction] == idx * DIRECTIONS + direction) { continue; }
+            for direction in start_directions(*heading) {
+                let state = idx * DIRECTIONS + direction;
+                cost[state] = 0;
+                prev[state] = state;
+                todo.push(Reverse((0, state)));
+            }
+        }
+        let mut end_state = None;
+        while let Some(Reverse((state_cost, state))) = todo.pop() {
+            if cost[state] < state_cost { continue; }
+            let (idx, direction) = (state / DIRECTIONS, state % DIRECTIONS);
+            if is_end[idx] {
+                end_state = Some(state);
+                break;
+            }
+            for next_direction in 0..DIRECTIONS {
+                if let Some(node) = self.neighbor(self.rc(idx), next_direction) {
+                    let next = self.idx(node.0, node.1) * DIRECTIONS + next_direction;
+                    let diff = (next_direction + DIRECTIONS - direction) % DIRECTIONS;
+                    let next_cost = state_cost + step + if diff == 0 { 0 } else { turn_cost(diff) };
+                    if next_cost < cost[next] {
+                        cost[next] = next_cost;
+                        prev[next] = state;
+                        todo.push(Reverse((next_cost, next)));
+                    }
+                }
+            }
+        }
+
+        let end_state = end_state?;
+        let mut path = VecDeque::new();
+        let mut state = end_state;
+        loop {
+            path.push_front((self.rc(state / DIRECTIONS), state % DIRECTIONS));
+            if prev[state] == state { break; }
+            state = prev[state];
+        }
+        Some(Found { cost: cost[end_state], path })
+    }
+
+    /// Finds the way with the fewest number of turns and, among such ways, with the fewest number of steps.
+    /// Returns number of turns, number of steps and the way (empty if not requested).
+    pub fn solve_shortest(&self, with_path: bool) -> Option<(DimType, usize, VecDeque<NodeAdr>)> {
+        if self.is_valid().is_err() { return None; }
+        // Every turn costs more than any possible number of steps, so that turns are minimized first
+        let turn = (self.data.len() * DIRECTIONS) as u64;
+        let found = self.search(&[(self.start, None)], &[self.end], 1, |_| turn)?;
+        let steps = found.path.len() - 1;
+        let path = if with_path { found.path.into_iter().map(|(node, _)| node).collect() } else { VecDeque::new() };
+        Some(((found.cost / turn) as DimType, steps, path))
+    }
+
+    /// Finds the cheapest way according to `model`, like `weighted::solve_weighted`.
+    /// Turns by 60 and 120 degrees clockwise are right turns, counterclockwise ones are left turns.
+    pub fn solve_weighted(&self, model: &CostModel, with_path: bool) -> Option<WeightedSolution> {
+        if self.is_valid().is_err() { return None; }
+        let found = self.search(&[(self.start, None)], &[self.end], model.step, |diff| match diff {
+            1 | 2 => model.right_turn,
+            3 => model.u_turn,
+            _ => model.left_turn,
+        })?;
+        let mut breakdown = CostBreakdown { steps: found.path.len() - 1, ..CostBreakdown::default() };
+        // The first move has no turn
+        for i in 2..found.path.len() {
+            match (found.path[i].1 + DIRECTIONS - found.path[i - 1].1) % DIRECTIONS {
+                0 => {},
+                1 | 2 => breakdown.right_turns += 1,
+                3 => breakdown.u_turns += 1,
+                _ => breakdown.left_turns += 1,
+            }
+        }
+        let path = if with_path { found.path.into_iter().map(|(node, _)| node).collect() } else { VecDeque::new() };
+        Some(WeightedSolution { cost: found.cost, breakdown, path })
+    }
+
+    /// Finds the way with the fewest number of turns which passes thru all `waypoints`, like `waypoints::solve_waypoints`.
+    pub fn solve_waypoints(&self, waypoints: &[NodeAdr], order: WaypointOrder, with_path: bool) -> Result<Option<(DimType, VecDeque<NodeAdr>)>, Error> {
+        if self.is_valid().is_err() { return Ok(None); }
+        waypoints::search(DIRECTIONS, |node, direction| self.neighbor(node, direction), self.start, self.end, waypoints, order, with_path)
+    }
+}
+
+impl Solver for Solver1 {
+
+    fn set_passage(&mut self, r: DimType, c: DimType) {
+        let idx = self.idx(r, c);
+        self.data[idx] = 1;
+    }
+
+    fn is_valid(&self) -> Result<(), Error> {
+        if !self.is_passage(self.start.0, self.start.1) { return Err(Error::StartBlocked(self.start)); }
+        if !self.is_passage(self.end.0, self.end.1) { return Err(Error::EndBlocked(self.end)); }
+        Ok(())
+    }
+
+    fn solve(&self, with_path: bool) -> Option<(DimType, VecDeque<NodeAdr>)> {
+        if self.is_valid().is_err() { return None; }
+        self.solve_multi(&[(self.start, None)], &[self.end], with_path)
+            .map(|solution| (solution.rank, solution.path))
+    }
+
+    /// Start heading up (down) means that the first move to either of neighbors in the row above (below) is not a turn.
+    fn solve_multi(&self, starts: &[StartPoint], ends: &[NodeAdr], with_path: bool) -> Option<MultiSolution> {
+        let found = self.search(starts, ends, 0, |_| 1)?;
+        let start_node = found.path[0].0;
+        let end_node = found.path[found.path.len() - 1].0;
+        Some(MultiSolution {
+            rank: found.cost as RankType,
+            start: starts.iter().position(|(node, _)| *node == start_node).expect("Logical error - path should begin at start"),
+            end: ends.iter().position(|node| *node == end_node).expect("Logical error - path should finish at end"),
+            path: if with_path { found.path.into_iter().map(|(node, _)| node).collect() } else { VecDeque::new() },
+        })
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::Maze;
+
+    fn open_solver(r: DimType, c: DimType) -> Solver1 {
+        let mut solv = Solver1::new_rc(r, c).unwrap();
+        for r in 0..r {
+            for c in 0..c {
+                solv.set_passage(r, c);
+            }
+        }
+        solv
+    }
+
+    #[test]
+    fn neighbor() {
+        let solv = open_solver(4, 4);
+        // Even row
+        assert_eq!((0..DIRECTIONS).map(|d| solv.neighbor((2, 1), d)).collect::<Vec<_>>(),
+            vec![Some((2, 2)), Some((3, 1)), Some((3, 0)), Some((2, 0)), Some((1, 0)), Some((1, 1))]);
+        // Odd row
+        assert_eq!((0..DIRECTIONS).map(|d| solv.neighbor((1, 1), d)).collect::<Vec<_>>(),
+            vec![Some((1, 2)), Some((2, 2)), Some((2, 1)), Some((1, 0)), Some((0, 1)), Some((0, 2))]);
+        // Edges
+        assert_eq!((0..DIRECTIONS).map(|d| solv.neighbor((0, 0), d)).collect::<Vec<_>>(),
+            vec![Some((0, 1)), Some((1, 0)), None, None, None, None]);
+        assert_eq!((0..DIRECTIONS).map(|d| solv.neighbor((3, 3), d)).collect::<Vec<_>>(),
+            vec![None, None, None, Some((3, 2)), Some((2, 3)), None]);
+    }
+
+    #[test]
+    fn solve() {
+        let maze: Maze = "4,4\n0000\n1100\n0111\n0000\n".parse().unwrap();
+        let mut solv = Solver1::new_rc(maze.rows(), maze.cols()).unwrap();
+        maze.load_into(&mut solv);
+        assert!(solv.is_valid().is_ok());
+        // Moving south-east from (1,0) leads to (2,1) as odd rows are shifted right
+        assert_eq!(solv.solve(true), Some((1, vec![(1, 0), (2, 1), (2, 2), (2, 3)].into_iter().collect())));
+        assert_eq!(solv.solve_shortest(false), Some((1, 3, VecDeque::new())));
+        let solution = solv.solve_weighted(&CostModel::default(), false).unwrap();
+        assert_eq!(solution.cost, 1);
+        assert_eq!(solution.breakdown, CostBreakdown { steps:3, left_turns:1, right_turns:0, u_turns:0 });
+        assert_eq!(solv.solve_waypoints(&[(2, 1)], WaypointOrder::Given, false).unwrap().map(|s| s.0), Some(1));
+        assert_eq!(
+            solv.solve_waypoints(&[(1, 1)], WaypointOrder::Given, true).unwrap(),
+            Some((2, vec![(1, 0), (1, 1), (2, 2), (2, 3)].into_iter().collect()))
+        );
+
+        let starts = [((1, 0), Some(Heading::Right))];
+        assert_eq!(solv.solve_multi(&starts, &[(2, 3)], false).map(|s| s.rank), Some(2));
+        let starts = [((0, 0), None), ((1, 0), Some(Heading::Down))];
+        assert_eq!(solv.solve_multi(&starts, &[(2, 3)], false).map(|s| (s.rank, s.start)), Some((1, 1)));
+
+        let solv = Solver1::new_rc(4, 4).unwrap();
+        assert!(solv.is_valid().is_err());
+        assert_eq!(solv.solve(false), None);
+    }
+
+    #[test]
+    fn solve_straight() {
+        // Straight line to south-east zigzags between columns
+        let mut solv = Solver1::new_with_endpoints(6, 4, (1, 0), (5, 2)).unwrap();
+        for node in [(1, 0), (2, 1), (3, 1), (4, 2), (5, 2)].iter() {
+            solv.set_passage(node.0, node.1);
+        }
+        assert_eq!(solv.solve(true), Some((0, vec![(1, 0), (2, 1), (3, 1), (4, 2), (5, 2)].into_iter().collect())));
+        // Every change of direction is a turn
+        let solv = open_solver(6, 6);
+        assert_eq!(solv.solve(false).map(|s| s.0), Some(1));
+    }
+}
@@ -0,0 +1,212 @@
+//! Maze with keys and doors: lowercase letters are keys and uppercase letters are doors,
+//! which may be passed only after the matching key was collected (key `a` opens door `A`).
+
+use std::collections::{HashMap, VecDeque};
+
+use super::*;
+use grid::Grid;
+
+/// Value of `data` for key cell is this plus number of the letter (0 for `a`), 0 is wall and 1 passage
+const KEY: u8 = 2;
+/// Value of `data` for door cell is this plus number of the letter
+const DOOR: u8 = KEY + 26;
+
+/// The simplest way found by `Solver1::solve`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeysSolution {
+    /// Number of turns
+    pub rank: DimType,
+    /// Keys collected along the way (only the ones which were not held yet), in order of pickup
+    pub keys: Vec<(char, NodeAdr)>,
+    /// Nodes of the way - empty if not requested
+    pub path: VecDeque<NodeAdr>,
+}
+
+pub struct Solver1 {
+    grid: Grid,
+}
+
+/// Search state: node index, heading of the move by which it was entered and set of held keys (bit per letter)
+type State = (usize, Heading, u32);
+
+impl Solver1 {
+
+    pub fn new_rc(r: DimType, c:DimType) -> Result<Self, Error> {
+        let (start, end) = default_endpoints(r, c);
+        Self::new_with_endpoints(r, c, start, end)
+    }
+
+    /// Creates solver for maze with given start and end points
+    pub fn new_with_endpoints(r: DimType, c:DimType, start: NodeAdr, end: NodeAdr) -> Result<Self, Error> {
+        Ok(Solver1 { grid: Grid::new_with_endpoints(r, c, start, end)? })
+    }
+
+    /// Bit of the key which given node holds
+    #[inline]
+    fn key_bit(&self, idx: usize) -> u32 {
+        match self.grid.data[idx] {
+            v if (KEY..DOOR).contains(&v) => 1 << (v - KEY),
+            _ => 0,
+        }
+    }
+
+    /// Returns index of neighbor node in given direction if it may be entered with given keys
+    #[inline]
+    fn neighbor(&self, idx: usize, heading: Heading, keys: u32) -> Option<usize> {
+        let n_idx = self.grid.step(idx, heading)?;
+        match self.grid.data[n_idx] {
+            0 => None,
+            v if v >= DOOR && keys & (1 << (v - DOOR)) == 0 => None,
+            _ => Some(n_idx),
+        }
+    }
+
+    pub fn set_passage(&mut self, r: DimType, c: DimType) {
+        self.grid.set_cell(r, c, 1);
+    }
+
+    /// Puts key (lowercase ASCII letter) at given node
+    pub fn set_key(&mut self, r: DimType, c: DimType, key: char) {
+        self.grid.set_cell(r, c, KEY + (key as u8 - b'a'));
+    }
+
+    /// Puts door opened by given key (lowercase ASCII letter) at given node
+    pub fn set_door(&mut self, r: DimType, c: DimType, key: char) {
+        self.grid.set_cell(r, c, DOOR + (key as u8 - b'a'));
+    }
+
+    /// Checks that both start and end points are passages (start may not be a door)
+    pub fn is_valid(&self) -> Result<(), Error> {
+        self.grid.is_valid()?;
+        if self.grid.data[self.grid.idx(self.grid.start)] >= DOOR { return Err(Error::StartBlocked(self.grid.start)); }
+        Ok(())
+    }
+
+    /// Finds the way with the fewest number of turns, collecting keys needed to pass the doors on it.
+    ///
+    /// Search runs on (node, heading, held keys) states with 0-1 BFS, like `dijkstra_speed::Solver1::k_best`.
+    /// Ways may come back for keys, so moving back to the previous node is a turn here.
+    pub fn solve(&self, with_path: bool) -> Option<KeysSolution> {
+        if self.is_valid().is_err() { return None; }
+        let mut states = HashMap::<State, (DimType, Option<State>)>::new();
+        let mut todo = VecDeque::new();
+        let start = self.grid.idx(self.grid.start);
+        // First move from start node in any direction is not a turn
+        for heading in Heading::ALL.iter() {
+            let state = (start, *heading, self.key_bit(start));
+            states.insert(state, (0, None));
+            todo.push_back((state, 0));
+        }
+        let end = self.grid.idx(self.grid.end);
+        let mut end_state = None;
+        while let Some((state, rank)) = todo.pop_front() {
+            if states[&state].0 < rank { continue; }
+            let (idx, heading, keys) = state;
+            if idx == end {
+                end_state = Some(state);
+                break;
+            }
+            for next_heading in Heading::ALL.iter() {
+                let n_idx = match self.neighbor(idx, *next_heading, keys) {
+                    Some(n_idx) => n_idx,
+                    None => continue,
+                };
+                let next = (n_idx, *next_heading, keys | self.key_bit(n_idx));
+                let next_rank = if *next_heading == heading { rank } else { rank + 1 };
+                let better = match states.get(&next) {
+                    Some((old_rank, _)) => next_rank < *old_rank,
+                    None => true,
+                };
+                if better {
+                    states.insert(next, (next_rank, Some(state)));
+                    if next_rank == rank { todo.push_front((next, next_rank)); } else { todo.push_back((next, next_rank)); }
+                }
+            }
+        }
+
+        let end_state = end_state?;
+        let mut keys = Vec::new();
+        let mut path = VecDeque::new();
+        let mut state = end_state;
+        loop {
+            let prev = states[&state].1;
+            let held_before = prev.map_or(0, |prev| prev.2);
+            if state.2 != held_before {
+                keys.push(((b'a' + (state.2 ^ held_before).trailing_zeros() as u8) as char, self.grid.rc(state.0)));
+            }
+            if with_path { path.push_front(self.grid.rc(state.0)); }
+            match prev {
+                Some(prev) => state = prev,
+                None => break,
+            }
+        }
+        keys.reverse();
+        Some(KeysSolution { rank: states[&end_state].0, keys, path })
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{rand_maze, reference_rank, count_turns};
+
+    /// Creates solver from rows of the maze in the input format
+    fn from_rows(rows: &[&str]) -> Solver1 {
+        let mut solv = Solver1::new_rc(rows.len() as DimType, rows[0].len() as DimType).unwrap();
+        for (r, row) in (0..).zip(rows.iter()) {
+            for (c, ch) in (0..).zip(row.chars()) {
+                match ch {
+                    '0' => {},
+                    'a'..='z' => solv.set_key(r, c, ch),
+                    'A'..='Z' => solv.set_door(r, c, ch.to_ascii_lowercase()),
+                    _ => solv.set_passage(r, c),
+                }
+            }
+        }
+        solv
+    }
+
+    #[test]
+    fn keys_and_doors() {
+        // Key is in the dead end above the corridor, before the door
+        let solv = from_rows(&["0a00000", "111A111", "0000001", "0000001", "0000000"]);
+        assert_eq!(
+            solv.solve(true),
+            Some(KeysSolution {
+                rank: 4,
+                keys: vec![('a', (0, 1))],
+                path: vec![(1, 0), (1, 1), (0, 1), (1, 1), (1, 2), (1, 3), (1, 4), (1, 5), (1, 6), (2, 6), (3, 6)].into_iter().collect(),
+            })
+        );
+        // Without the key there is no way
+        assert_eq!(from_rows(&["0000000", "111A111", "0000001", "0000001", "0000000"]).solve(false), None);
+        // Key on the way, and the other key which is not needed
+        let solution = from_rows(&["0000b00", "1a1A1B1", "0000001", "0000001", "0000000"]).solve(false).unwrap();
+        assert_eq!(solution.rank, 4);
+        assert_eq!(solution.keys, vec![('a', (1, 1)), ('b', (0, 4))]);
+        let solution = from_rows(&["0000000", "1a1A1b1", "0000001", "0000001", "0000000"]).solve(false).unwrap();
+        assert_eq!((solution.rank, solution.keys), (1, vec![('a', (1, 1)), ('b', (1, 5))]));
+        assert!(matches!(from_rows(&["000", "A11", "000"]).is_valid(), Err(Error::StartBlocked((1, 0)))));
+    }
+
+    #[test]
+    fn cross_check() {
+        // Without keys and doors the result is the same as of other solvers,
+        // except ways with moving back which bfs01 does not count as a turn - such ways are never needed here
+        let maze = rand_maze(30, 75);
+        let mut solv = Solver1::new_rc(maze.rows(), maze.cols()).unwrap();
+        for (r, c) in maze.passages() {
+            solv.set_passage(r, c);
+        }
+        let solution = solv.solve(true);
+        assert_eq!(solution.as_ref().map(|s| s.rank), reference_rank(&maze));
+        if let Some(KeysSolution { rank, keys, path }) = solution {
+            assert!(keys.is_empty());
+            assert_eq!(count_turns(&path), rank);
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Layered (3-D) maze: levels of 2-D grids stacked one above another,
+//! where besides 4 horizontal neighbors also the nodes directly above and below are reachable.
+//! Change between horizontal and vertical movement counts as a turn, the same as change of direction within a level.
+
+use std::collections::VecDeque;
+
+use super::*;
+
+/// To store number of turns
+type RankType = DimType;
+
+/// Level, row and column change of move in each of 6 directions
+const MOVES: [(i32, i32, i32); 6] = [(0, -1, 0), (0, 0, 1), (0, 1, 0), (0, 0, -1), (-1, 0, 0), (1, 0, 0)];
+
+/// Axis of move in given direction: 0 - between levels, 1 - along column, 2 - along row
+#[inline]
+fn axis(direction: usize) -> usize {
+    match direction {
+        0 | 2 => 1,
+        1 | 3 => 2,
+        _ => 0,
+    }
+}
+
+pub struct Solver1 {
+    data: Vec<u8>,
+    z_dim: DimType,
+    r_dim: DimType,
+    c_dim: DimType,
+    start: NodeAdr3,
+    end: NodeAdr3,
+}
+
+impl Solver1 {
+
+    /// Creates solver for maze with start point at the leftmost node in the second row of the first level
+    /// and end point at the rightmost node in the second last row of the last level
+    pub fn new_zrc(z: DimType, r: DimType, c: DimType) -> Result<Self, Error> {
+        let ((r_start, c_start), (r_end, c_end)) = default_endpoints(r, c);
+        Self::new_with_endpoints(z, r, c, (0, r_start, c_start), (z.saturating_sub(1), r_end, c_end))
+    }
+
+    /// Creates solver for maze with given start and end points
+    pub fn new_with_endpoints(z: DimType, r: DimType, c: DimType, start: NodeAdr3, end: NodeAdr3) -> Result<Self, Error> {
+        if z < 1 { return Err(Error::TooFewLevels(z)); }
+        if r < 2 || c < 2 { return Err(Error::TooSmallDimensions { rows:r, cols:c }); }
+        // Rough estimation of maximal number of turns, as in 2-D solvers
+        if (z as usize + r as usize + c as usize) / 2 > RankType::MAX as usize { return Err(Error::TooBigDimensions { rows:r, cols:c }); }
+        for node in [start, end].iter() {
+            if node.0 >= z || node.1 >= r || node.2 >= c { return Err(Error::EndpointOutOfRange3(*node)); }
+        }
+        Ok(Solver1 {
+            data: vec![0; z as usize * r as usize * c as usize],
+            z_dim:z,
+            r_dim:r,
+            c_dim:c,
+            start,
+            end,
+        })
+    }
+
+    #[inline]
+    fn idx(&self, (z, r, c): NodeAdr3) -> usize {
+        (z as usize * self.r_dim as usize + r as usize) * self.c_dim as usize + c as usize
+    }
+
+    #[inline]
+    fn zrc(&self, idx: usize) -> NodeAdr3 {
+        let c = idx % self.c_dim as usize;
+        let zr = idx / self.c_dim as usize;
+        ((zr / self.r_dim as usize) as DimType, (zr % self.r_dim as usize) as DimType, c as DimType)
+    }
+
+    #[inline]
+    fn is_passage(&self, node: NodeAdr3) -> bool {
+        self.data[self.idx(node)] == 1
+    }
+
+    /// Returns neighbor node in given direction if it is a passage
+    #[inline]
+    fn neighbor(&self, (z, r, c): NodeAdr3, direction: usize) -> Option<NodeAdr3> {
+        let (dz, dr, dc) = MOVES[direction];
+        let (z, r, c) = (z as i32 + dz, r as i32 + dr, c as i32 + dc);
+        if z < 0 || r < 0 || c < 0 || z >= self.z_dim as i32 || r >= self.r_dim as i32 || c >= self.c_dim as i32 { return None; }
+        let node = (z as DimType, r as DimType, c as DimType);
+        if self.is_passage(node) { Some(node) } else { None }
+    }
+
+    pub fn set_passage(&mut self, z: DimType, r: DimType, c: DimType) {
+        let idx = self.idx((z, r, c));
+        self.data[idx] = 1;
+    }
+
+    /// Checks that both start and end points are passages
+    pub fn is_valid(&self) -> Result<(), Error> {
+        if !self.is_passage(self.start) { return Err(Error::StartBlocked3(self.start)); }
+        if !self.is_passage(self.end) { return Err(Error::EndBlocked3(self.end)); }
+        Ok(())
+    }
+
+    /// Finds the way with the fewest number of turns.
+    /// Returns number of turns and the way (empty if not requested).
+    ///
+    /// 0-1 BFS on (node, axis) states, like `bfs01`, with 3 axes instead of 2.
+    pub fn solve(&self, with_path: bool) -> Option<(DimType, VecDeque<NodeAdr3>)> {
+        if self.is_valid().is_err() { return None; }
+        // State = node index * 3 + axis
+        let mut rank = vec![RankType::MAX; self.data.len() * 3];
+        let mut prev = vec![usize::MAX; self.data.len() * 3];
+        let mut todo = VecDeque::new();
+        // First move from start node in any direction is not a turn
+        for axis in 0..3 {
+            let state = self.idx(self.start) * 3 + axis;
+            rank[state] = 0;
+            prev[state] = state;
+            todo.push_back(state);
+        }
+        let mut end_state = None;
+        while let Some(state) = todo.pop_front() {
+            let node = self.zrc(state / 3);
+            if node == self.end {
+                end_state = Some(state);
+                break;
+            }
+            for direction in 0..MOVES.len() {
+                let next_node = match self.neighbor(node, direction) {
+                    Some(next_node) => next_node,
+                    None => continue,
+                };
+                let next = self.idx(next_node) * 3 + axis(direction);
+                let turn = state % 3 != axis(direction);
+                let next_rank = if turn { rank[state] + 1 } else { rank[state] };
+                if next_rank < rank[next] {
+                    rank[next] = next_rank;
+                    prev[next] = state;
+                    if turn { todo.push_back(next); } else { todo.push_front(next); }
+                }
+            }
+        }
+
+        let end_state = end_state?;
+        let mut path = VecDeque::new();
+        if with_path {
+            let mut state = end_state;
+            path.push_front(self.zrc(state / 3));
+            while prev[state] != state {
+                state = prev[state];
+                path.push_front(self.zrc(state / 3));
+            }
+        }
+        Some((rank[end_state], path))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{rand_maze, reference_rank};
+
+    #[test]
+    fn stairs() {
+        // Corridor at level 0 leads to the shaft, which goes down to the corridor at level 2
+        let mut solv = Solver1::new_zrc(3, 4, 4).unwrap();
+        for node in [(0, 1, 0), (0, 1, 1), (1, 1, 1), (2, 1, 1), (2, 2, 1), (2, 2, 2), (2, 2, 3)].iter() {
+            solv.set_passage(node.0, node.1, node.2);
+        }
+        assert!(solv.is_valid().is_ok());
+        assert_eq!(
+            solv.solve(true),
+            Some((3, vec![(0, 1, 0), (0, 1, 1), (1, 1, 1), (2, 1, 1), (2, 2, 1), (2, 2, 2), (2, 2, 3)].into_iter().collect()))
+        );
+
+        // Straight way within the first level
+        let mut solv = Solver1::new_with_endpoints(3, 4, 4, (0, 1, 0), (0, 1, 3)).unwrap();
+        for c in 0..4 {
+            solv.set_passage(0, 1, c);
+        }
+        assert_eq!(solv.solve(false), Some((0, VecDeque::new())));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(Solver1::new_zrc(0, 4, 4), Err(Error::TooFewLevels(0))));
+        assert_eq!(Solver1::new_zrc(0, 4, 4).err().unwrap().to_string(), "Too small dimensions: 0 levels");
+        assert!(matches!(Solver1::new_zrc(1, 1, 4), Err(Error::TooSmallDimensions { rows:1, cols:4 })));
+        assert!(matches!(Solver1::new_with_endpoints(2, 4, 4, (2, 1, 0), (0, 2, 3)), Err(Error::EndpointOutOfRange3((2, 1, 0)))));
+        let mut solv = Solver1::new_zrc(2, 4, 4).unwrap();
+        assert!(matches!(solv.is_valid(), Err(Error::StartBlocked3((0, 1, 0)))));
+        solv.set_passage(0, 1, 0);
+        assert!(matches!(solv.is_valid(), Err(Error::EndBlocked3((1, 2, 3)))));
+        solv.set_passage(1, 2, 3);
+        assert_eq!(solv.solve(false), None);
+    }
+
+    #[test]
+    fn cross_check() {
+        // Single level maze has the same solution as 2-D one
+        let maze = rand_maze(40, 75);
+        let mut solv = Solver1::new_zrc(1, maze.rows(), maze.cols()).unwrap();
+        for (r, c) in maze.passages() {
+            solv.set_passage(0, r, c);
+        }
+        let solution = solv.solve(false);
+        assert_eq!(solution.as_ref().map(|s| s.0), reference_rank(&maze));
+
+        // The same maze repeated on two levels: going to the other level costs exactly one more turn
+        let mut solv = Solver1::new_zrc(2, maze.rows(), maze.cols()).unwrap();
+        for z in 0..2 {
+            for (r, c) in maze.passages() {
+                solv.set_passage(z, r, c);
+            }
+        }
+        let solution3 = solv.solve(true);
+        assert_eq!(solution3.as_ref().map(|s| s.0), solution.map(|s| s.0 + 1));
+        if let Some((_, path)) = solution3 {
+            assert!(path.iter().zip(path.iter().skip(1)).all(|(a, b)|
+                (a.0 as i32 - b.0 as i32).abs() + (a.1 as i32 - b.1 as i32).abs() + (a.2 as i32 - b.2 as i32).abs() == 1));
+        }
+    }
+}
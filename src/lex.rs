@@ -0,0 +1,122 @@
+//! Lexicographic objective: the fewest number of turns first, then the fewest number of steps.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+use super::*;
+use maze::Maze;
+
+/// Finds the way from `start` to `end` with the fewest number of turns, and among them the one with the fewest
+/// number of steps. Returns number of turns, number of steps and the way (empty if not requested),
+/// or `None` also if start or end point is not a passage of the maze.
+///
+/// Search runs on (node, axis) states, like `bfs01`, but with ranks being pairs (turns, steps) compared
+/// lexicographically. Moves cost (0, 1) or (1, 1), so plain Dijkstra with binary heap is used.
+/// Moving back and forth along the same axis costs no turn here, but such way is never the shortest one.
+pub fn solve_shortest(maze: &Maze, start: NodeAdr, end: NodeAdr, with_path: bool) -> Option<(DimType, usize, VecDeque<NodeAdr>)> {
+    if maze.check_endpoints(start, end).is_err() { return None; }
+    let (r_dim, c_dim) = (maze.rows(), maze.cols());
+    let c_dim_u = c_dim as usize;
+    let idx = |(r, c): NodeAdr| r as usize * c_dim_u + c as usize;
+    let rc = |idx: usize| ((idx / c_dim_u) as DimType, (idx % c_dim_u) as DimType);
+    let len = r_dim as usize * c_dim_u;
+    // State = node index * 2 + axis (0 - vertical, 1 - horizontal)
+    let mut rank = vec![(DimType::MAX, usize::MAX); len * 2];
+    let mut prev = vec![usize::MAX; len * 2];
+    let mut todo = BinaryHeap::new();
+    // First move from start node in any direction is not a turn
+    for axis in 0..2 {
+        let state = idx(start) * 2 + axis;
+        rank[state] = (0, 0);
+        prev[state] = state;
+        todo.push(Reverse((0, 0, state)));
+    }
+    let mut end_state = None;
+    while let Some(Reverse((turns, steps, state))) = todo.pop() {
+        if rank[state] < (turns, steps) { continue; }
+        let (r, c) = rc(state / 2);
+        if (r, c) == end {
+            end_state = Some(state);
+            break;
+        }
+        let neighbors = [
+            (r > 0, (r.wrapping_sub(1), c), 0),
+            (c < c_dim - 1, (r, c.wrapping_add(1)), 1),
+            (r < r_dim - 1, (r.wrapping_add(1), c), 0),
+            (c > 0, (r, c.wrapping_sub(1)), 1),
+        ];
+        for (inside, node, axis) in neighbors.iter() {
+            if !inside || !maze.is_passage(node.0, node.1) { continue; }
+            let next = idx(*node) * 2 + axis;
+            let next_rank = (if state % 2 == *axis { turns } else { turns + 1 }, steps + 1);
+            if next_rank < rank[next] {
+                rank[next] = next_rank;
+                prev[next] = state;
+                todo.push(Reverse((next_rank.0, next_rank.1, next)));
+            }
+        }
+    }
+
+    let end_state = end_state?;
+    let mut path = VecDeque::new();
+    if with_path {
+        let mut state = end_state;
+        path.push_front(rc(state / 2));
+        while prev[state] != state {
+            state = prev[state];
+            path.push_front(rc(state / 2));
+        }
+    }
+    let (turns, steps) = rank[end_state];
+    Some((turns, steps, path))
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{rand_maze, count_turns};
+
+    #[test]
+    fn shortest() {
+        // Two ways around the wall at (2,2) with 4 turns, the lower one is longer
+        let mut maze = Maze::new_rc(7, 5);
+        for node in [(2, 0), (2, 1), (2, 3), (2, 4), (1, 2), (5, 2)].iter() {
+            maze.set_passage(node.0, node.1);
+        }
+        for r in 1..6 {
+            maze.set_passage(r, 1);
+            maze.set_passage(r, 3);
+        }
+        assert_eq!(
+            solve_shortest(&maze, (2, 0), (2, 4), true),
+            Some((4, 6, vec![(2, 0), (2, 1), (1, 1), (1, 2), (1, 3), (2, 3), (2, 4)].into_iter().collect()))
+        );
+        assert_eq!(solve_shortest(&maze, (2, 0), (2, 4), false), Some((4, 6, VecDeque::new())));
+        assert_eq!(solve_shortest(&maze, (2, 0), (0, 0), false), None);
+        assert_eq!(solve_shortest(&maze, (2, 0), (7, 0), false), None);
+        assert_eq!(solve_shortest(&Maze::new_rc(3, 3), (1, 0), (1, 2), false), None);
+    }
+
+    #[test]
+    fn cross_check() {
+        let maze = rand_maze(40, 75);
+        let mut solv = bfs01::Solver1::new_rc(maze.rows(), maze.cols()).unwrap();
+        maze.load_into(&mut solv);
+        let (start, end) = default_endpoints(maze.rows(), maze.cols());
+        let solution = solv.solve(true);
+        let shortest = solve_shortest(&maze, start, end, true);
+        assert_eq!(solution.as_ref().map(|s| s.0), shortest.as_ref().map(|s| s.0));
+        if let (Some((_, path)), Some((turns, steps, shortest_path))) = (solution, shortest) {
+            assert!(steps < path.len());
+            assert_eq!(steps + 1, shortest_path.len());
+            assert_eq!(count_turns(&shortest_path), turns);
+            // Every step goes to neighbor passage
+            assert!(shortest_path.iter().zip(shortest_path.iter().skip(1))
+                .all(|(a, b)| (a.0 as i32 - b.0 as i32).abs() + (a.1 as i32 - b.1 as i32).abs() == 1 && maze.is_passage(b.0, b.1)));
+        }
+    }
+}
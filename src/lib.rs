@@ -0,0 +1,255 @@
+//! Finding the way thru the maze with the fewest number of turns.
+//!
+//! The maze is loaded into one of [`Solver`] implementations (node by node with [`Solver::set_passage`]),
+//! e.g. directly from the text format with [`maze::read_rows`], or from parsed [`maze::Maze`].
+
+use std::collections::VecDeque;
+
+pub mod error;
+pub mod maze;
+pub mod dijkstra_speed;
+pub mod dijkstra_mem;
+pub mod dijkstra_dir;
+pub mod bfs01;
+pub mod corridor_sweep;
+pub mod hex;
+pub mod layered;
+pub mod breaking;
+pub mod keys;
+pub mod straight_run;
+pub mod any_solver;
+pub mod weighted;
+pub mod diagonal;
+pub mod waypoints;
+pub mod lex;
+mod grid;
+
+pub use error::Error;
+pub use any_solver::{AnySolver, SOLVER_NAMES};
+pub use weighted::{CostModel, CostBreakdown, WeightedSolution};
+pub use diagonal::{EightConnected, CornerCutting};
+pub use waypoints::WaypointOrder;
+
+/// To store dimension of puzzle
+/// must be castable to usize
+pub type DimType = u16;
+
+/// Node address: (row, column)
+pub type NodeAdr = (DimType, DimType);
+
+/// Node address in layered (3-D) maze: (level, row, column)
+pub type NodeAdr3 = (DimType, DimType, DimType);
+
+/// Direction of the move by which node was entered
+/// (used by solvers which keep it as part of the search state)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Heading {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Heading {
+    pub const ALL: [Heading; 4] = [Heading::Up, Heading::Right, Heading::Down, Heading::Left];
+
+    /// Heading of the move back to the previous node
+    pub fn opposite(self) -> Heading {
+        Heading::ALL[(self as usize + 2) % 4]
+    }
+}
+
+/// How moving back to the previous node (U-turn) is handled by solvers which distinguish it from other turns
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Reversal {
+    /// Counts as one turn, like any other change of direction
+    #[default]
+    Turn,
+    /// Counts as two turns - the same as two consecutive turns by 90 degrees
+    DoubleTurn,
+    /// Moving back is not allowed
+    Forbidden,
+}
+
+/// Shape of the maze borders, for solvers which support other than the challenge one
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Topology {
+    /// Maze ends at its borders
+    #[default]
+    Bounded,
+    /// Stepping off one edge re-enters the maze on the opposite edge of the same row or column
+    Toroidal,
+}
+
+/// Cost of entering the cell, kept on top of the passage grid by solvers which support cell-cost layer
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CellCost {
+    /// Ordinary cell
+    #[default]
+    Free,
+    /// Passable cell which adds given penalty to the cost of the way entering it (mud, congestion)
+    Penalty(u32),
+    /// Cell which may not be entered, even if it is a passage (e.g. temporarily closed)
+    Forbidden,
+}
+
+/// Start point for `Solver::solve_multi`: node and optional initial heading.
+/// If heading is given, the first move in any other direction counts as a turn
+/// (also the move back, which with some reversal policies of `dijkstra_dir` costs more or is forbidden),
+/// otherwise the first move is free in any direction.
+pub type StartPoint = (NodeAdr, Option<Heading>);
+
+/// The simplest way found by `Solver::solve_multi`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MultiSolution {
+    /// Number of turns
+    pub rank: DimType,
+    /// Index of used start in `starts` argument
+    pub start: usize,
+    /// Index of used end in `ends` argument
+    pub end: usize,
+    /// Nodes of the way - empty if not requested
+    pub path: VecDeque<NodeAdr>,
+}
+
+/// Start and end points of the original challenge:
+/// leftmost node in the second row and rightmost node in the second last row
+pub fn default_endpoints(r: DimType, c: DimType) -> (NodeAdr, NodeAdr) {
+    ((1, 0), (r.saturating_sub(2), c.saturating_sub(1)))
+}
+
+/// Returns fake previous node of start point for solvers which detect turns by comparing coordinates
+/// of the previous, current and next node (see `is_turn`): the node itself if any first move is free,
+/// or the node behind start for given heading (wrapped around the borders of toroidal maze).
+/// Fake node may lie outside of the bounded maze, but only its coordinates are compared.
+pub(crate) fn start_prev(node: NodeAdr, heading: Option<Heading>, r_dim: DimType, c_dim: DimType, topology: Topology) -> NodeAdr {
+    let wrap = topology == Topology::Toroidal;
+    match heading {
+        None => node,
+        Some(Heading::Up) if wrap && node.0 == r_dim - 1 => (0, node.1),
+        Some(Heading::Up) => (node.0.wrapping_add(1), node.1),
+        Some(Heading::Right) if wrap && node.1 == 0 => (node.0, c_dim - 1),
+        Some(Heading::Right) => (node.0, node.1.wrapping_sub(1)),
+        Some(Heading::Down) if wrap && node.0 == 0 => (r_dim - 1, node.1),
+        Some(Heading::Down) => (node.0.wrapping_sub(1), node.1),
+        Some(Heading::Left) if wrap && node.1 == c_dim - 1 => (node.0, 0),
+        Some(Heading::Left) => (node.0, node.1.wrapping_add(1)),
+    }
+}
+
+/// Checks whether the move from `cur` to `next` is a turn, when `cur` was entered from `prev`.
+/// Moving back to `prev` is a turn too - it matters only for the first move from start point with heading,
+/// as otherwise `prev` is already fixed when `cur` is expanded.
+#[inline]
+pub(crate) fn is_turn(prev: NodeAdr, cur: NodeAdr, next: NodeAdr) -> bool {
+    next == prev || !((next.0 == cur.0 && cur.0 == prev.0) || (next.1 == cur.1 && cur.1 == prev.1))
+}
+
+/// Checks that start and end points lie within the maze of given dimensions
+pub(crate) fn check_endpoints(r: DimType, c: DimType, start: NodeAdr, end: NodeAdr) -> Result<(), Error> {
+    for node in [start, end].iter() {
+        if node.0 >= r || node.1 >= c { return Err(Error::EndpointOutOfRange(*node)); }
+    }
+    Ok(())
+}
+
+// Sized is required in order to provide default implementation of solve_and_drop
+pub trait Solver:Sized {
+    fn set_passage(&mut self, r: DimType, c:DimType);
+    /// Checks that both start and end points are passages
+    fn is_valid(&self) -> Result<(), Error>;
+    fn solve(&self, with_path: bool) -> Option<(DimType, VecDeque<NodeAdr>)>;
+    /// Finds the simplest way from any of `starts` to any of `ends` (instead of solver's start and end points).
+    /// Nodes which are not passages or lie outside of the maze are ignored;
+    /// if the same node is given more than once, its first occurrence is used.
+    fn solve_multi(&self, starts: &[StartPoint], ends: &[NodeAdr], with_path: bool) -> Option<MultiSolution>;
+    fn solve_and_drop(self, with_path: bool) -> Option<(DimType, VecDeque<NodeAdr>)> {
+        self.solve(with_path)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use ndarray::{Array, ArrayView2};
+    use ndarray_rand::RandomExt;
+    use ndarray_rand::rand_distr::Uniform;
+    use super::*;
+    
+    pub fn init_from_rand_array<A:PartialOrd>(solv:&mut impl Solver, arr:&ArrayView2<A>, frac:A) {
+        let dim = arr.shape();
+        for (c, v) in arr.indexed_iter() {
+            if *v <= frac {
+                solv.set_passage(c.0 as DimType, c.1 as DimType);
+            }
+        }
+        solv.set_passage(1, 0);
+        solv.set_passage(dim[0] as DimType - 2, dim[1] as DimType - 1);
+        assert!(solv.is_valid().is_ok());
+    }
+
+    /// Random maze for cross checks with dimensions from 5 up to `max_dim` (excluded), where cells with random value
+    /// (from 0 to 100) up to `frac` are passages, as well as start and end points of the challenge
+    pub fn rand_maze(max_dim: usize, frac: u32) -> maze::Maze {
+        let mut rng = rand::thread_rng();
+        let r_dim = rng.gen_range(5, max_dim);
+        let c_dim = rng.gen_range(5, max_dim);
+        let arr = Array::random((r_dim, c_dim), Uniform::new_inclusive(0, 100));
+        let mut maze = maze::Maze::new_rc(r_dim as DimType, c_dim as DimType);
+        for ((r, c), v) in arr.indexed_iter() {
+            if *v <= frac { maze.set_passage(r as DimType, c as DimType); }
+        }
+        let (start, end) = default_endpoints(r_dim as DimType, c_dim as DimType);
+        maze.set_passage(start.0, start.1);
+        maze.set_passage(end.0, end.1);
+        maze
+    }
+
+    /// Number of turns of the simplest way thru the maze found by `bfs01`, as the reference for cross checks
+    pub fn reference_rank(maze: &maze::Maze) -> Option<DimType> {
+        let mut solv = bfs01::Solver1::new_rc(maze.rows(), maze.cols()).unwrap();
+        maze.load_into(&mut solv);
+        solv.solve(false).map(|s| s.0)
+    }
+
+    pub fn count_turns(path:&VecDeque<NodeAdr>) -> DimType {
+        let mut turns = 0;
+        for i in 2..path.len() {
+            let d1 = (path[i - 1].0 as isize - path[i - 2].0 as isize, path[i - 1].1 as isize - path[i - 2].1 as isize);
+            let d2 = (path[i].0 as isize - path[i - 1].0 as isize, path[i].1 as isize - path[i - 1].1 as isize);
+            if d1 != d2 { turns += 1; }
+        }
+        turns
+    }
+
+    #[test]
+    #[allow(clippy::assertions_on_constants, clippy::absurd_extreme_comparisons)]
+    fn size_constraints() {
+        assert!(DimType::MAX >= 10_000);
+        assert!((DimType::MAX as usize)^2 <= usize::MAX);
+        assert_eq!(dijkstra_speed::Solver1::new_rc(10_000, 10_000).is_ok(), true);
+        assert_eq!(dijkstra_mem::Solver1::new_rc(10_000, 10_000).is_ok(), true);
+        assert_eq!(dijkstra_dir::Solver1::new_rc(10_000, 10_000).is_ok(), true);
+        assert_eq!(bfs01::Solver1::new_rc(10_000, 10_000).is_ok(), true);
+        assert_eq!(corridor_sweep::Solver1::new_rc(10_000, 10_000).is_ok(), true);
+        assert_eq!(hex::Solver1::new_rc(10_000, 10_000).is_ok(), true);
+    }
+    
+    #[test]
+    fn cross_check() {
+        let mut rng = rand::thread_rng();
+        let r_dim = rng.gen_range(5, 20);
+        let c_dim = rng.gen_range(5, 20);
+        let arr = Array::random((r_dim, c_dim), Uniform::new_inclusive(0, 100));
+        let mut solv1 = dijkstra_speed::Solver1::new_rc(r_dim as DimType, c_dim as DimType).unwrap();
+        let mut solv2 = dijkstra_mem::Solver1::new_rc(r_dim as DimType, c_dim as DimType).unwrap();
+        init_from_rand_array(&mut solv1, &arr.view(), 75);
+        init_from_rand_array(&mut solv2, &arr.view(), 75);
+        assert_eq!(solv1.solve(false), solv2.solve(false));
+    }
+    
+}
@@ -1,49 +1,410 @@
 use std::env;
-use std::io;
-use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::process;
 
-mod dijkstra_speed;
-mod dijkstra_mem;
+use gpw_challenge::{DimType, NodeAdr, Error, Solver, AnySolver, SOLVER_NAMES};
+use gpw_challenge::{maze, lex, hex, layered, breaking, keys, dijkstra_speed, straight_run};
 
-fn solver_factory(rows: DimType, cols: DimType) -> Result<impl Solver, &'static str> {
-    dijkstra_speed::Solver1::new_rc(rows, cols)
-    //dijkstra_mem::Solver1::new_rc(rows, cols)
+fn solver_factory(name: &str, hex: bool, rows: DimType, cols: DimType, start: Option<NodeAdr>, end: Option<NodeAdr>) -> Result<AnySolver, Error> {
+    let (default_start, default_end) = gpw_challenge::default_endpoints(rows, cols);
+    let (start, end) = (start.unwrap_or(default_start), end.unwrap_or(default_end));
+    if hex {
+        hex::Solver1::new_with_endpoints(rows, cols, start, end).map(AnySolver::Hex)
+    } else {
+        AnySolver::new_with_endpoints(name, rows, cols, start, end)
+    }
 }
 
-fn main() -> Result<(), isize> {
-    let args: Vec<String> = env::args().collect();
-    let mut arg_dbg = false;
-    let mut arg_converter = false;
-    if args.len() > 1 {
-        arg_dbg = args[1] == "--dbg";
-        arg_converter = !arg_dbg;
+/// Parses node address given as `row,column` (or `level,row,column` for layered maze)
+fn parse_node(option: &str, arg: Option<&String>) -> Result<Vec<DimType>, Error> {
+    let err = || Error::InvalidArgument(format!("Option {} requires node address: <row>,<column>", option));
+    let arg = arg.ok_or_else(err)?;
+    let rc = arg.split(',')
+        .map(|s| s.trim().parse::<DimType>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| err())?;
+    if rc.len() != 2 && rc.len() != 3 { return Err(err()); }
+    Ok(rc)
+}
+
+/// Checks that node address given with option has `len` coordinates
+fn check_node(option: &str, node: &Option<Vec<DimType>>, len: usize) -> Result<(), Error> {
+    match node {
+        Some(node) if node.len() != len => {
+            let adr = if len == 2 { "<row>,<column>" } else { "<level>,<row>,<column>" };
+            Err(Error::InvalidArgument(format!("Option {} requires node address: {}", option, adr)))
+        },
+        _ => Ok(()),
     }
+}
 
-    let stdin = io::stdin();
-    let mut buf = String::new();
-    if let Err(e) = stdin.read_line(&mut buf) {
-        eprintln!("Input line 1: {}", e);
-        return Err(-2);
+/// Solves layered maze: reads its levels from input and prints the result
+fn run_layered(input: &mut impl BufRead, levels: DimType, rows: DimType, cols: DimType,
+    start: Option<Vec<DimType>>, end: Option<Vec<DimType>>, dbg: bool) -> Result<bool, Error>
+{
+    check_node("--start", &start, 3)?;
+    check_node("--end", &end, 3)?;
+    let mut solver = match (start, end) {
+        (None, None) => layered::Solver1::new_zrc(levels, rows, cols)?,
+        (start, end) => {
+            let ((r_start, c_start), (r_end, c_end)) = gpw_challenge::default_endpoints(rows, cols);
+            let start = start.map_or((0, r_start, c_start), |n| (n[0], n[1], n[2]));
+            let end = end.map_or((levels.saturating_sub(1), r_end, c_end), |n| (n[0], n[1], n[2]));
+            layered::Solver1::new_with_endpoints(levels, rows, cols, start, end)?
+        },
+    };
+    maze::read_levels(input, levels, rows, cols, |z, r, c| solver.set_passage(z, r, c))?;
+    solver.is_valid()?;
+    if let Some((result, path)) = solver.solve(dbg) {
+        println!("{}", result);
+        if dbg {
+            println!("{:?}", path);
+        }
+        Ok(true)
+    } else {
+        Ok(false)
     }
-    #[allow(non_snake_case)]
-    let XY: Vec<_> = buf.split(',')
-        .map(|s| DimType::from_str_radix(s.trim(), 10))
-        .map(|n| n.map_err(|e| {eprintln!("Input line 1: parsing error: {}", e); -2isize}))
-        .collect();
-    if XY.len() < 2 {
-        eprintln!("Input line 1: should have 2 comma separated elements");
-        return Err(-2);
+}
+
+/// Solves maze choosing the shortest of the ways with the fewest turns: reads its rows from input
+/// and prints the number of turns together with the number of steps
+fn run_shortest(input: &mut impl BufRead, rows: DimType, cols: DimType, hex: bool,
+    start: Option<NodeAdr>, end: Option<NodeAdr>, dbg: bool) -> Result<bool, Error>
+{
+    let (default_start, default_end) = gpw_challenge::default_endpoints(rows, cols);
+    let (start, end) = (start.unwrap_or(default_start), end.unwrap_or(default_end));
+    let one_way_error = || Error::InvalidArgument("Option --shortest is not supported for maze with one-way cells".to_string());
+    let mut one_way = false;
+    let solution = if hex {
+        let mut solver = hex::Solver1::new_with_endpoints(rows, cols, start, end)?;
+        maze::read_rows_one_way(input, rows, cols, |r, c| solver.set_passage(r, c), |_, _, _| one_way = true)?;
+        if one_way { return Err(one_way_error()); }
+        solver.is_valid()?;
+        solver.solve_shortest(dbg)
+    } else {
+        let mut maze = maze::Maze::new_rc(rows, cols);
+        maze::read_rows_one_way(input, rows, cols, |r, c| maze.set_passage(r, c), |_, _, _| one_way = true)?;
+        if one_way { return Err(one_way_error()); }
+        maze.check_endpoints(start, end)?;
+        lex::solve_shortest(&maze, start, end, dbg)
+    };
+    if let Some((result, steps, path)) = solution {
+        println!("{} {}", result, steps);
+        if dbg {
+            println!("{:?}", path);
+        }
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+/// Solves maze allowing to break thru up to `k` walls: reads its rows from input and prints the result
+fn run_breaking(input: &mut impl BufRead, rows: DimType, cols: DimType, k: usize,
+    start: Option<NodeAdr>, end: Option<NodeAdr>, dbg: bool) -> Result<bool, Error>
+{
+    let (default_start, default_end) = gpw_challenge::default_endpoints(rows, cols);
+    let mut solver = breaking::Solver1::new_with_endpoints(rows, cols, start.unwrap_or(default_start), end.unwrap_or(default_end))?;
+    maze::read_rows(input, rows, cols, |r, c| solver.set_passage(r, c))?;
+    solver.is_valid()?;
+    if let Some(solution) = solver.solve(k, dbg)? {
+        println!("{}", solution.rank);
+        if dbg {
+            println!("{:?}", solution.path);
+            println!("{:?}", solution.broken);
+        }
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+/// Solves maze with keys and doors: reads its rows from input and prints the result
+fn run_keys(input: &mut impl BufRead, rows: DimType, cols: DimType,
+    start: Option<NodeAdr>, end: Option<NodeAdr>, dbg: bool) -> Result<bool, Error>
+{
+    let (default_start, default_end) = gpw_challenge::default_endpoints(rows, cols);
+    let mut solver = keys::Solver1::new_with_endpoints(rows, cols, start.unwrap_or(default_start), end.unwrap_or(default_end))?;
+    maze::read_rows_keys(input, rows, cols, |r, c, ch| match ch {
+        'a'..='z' => solver.set_key(r, c, ch),
+        'A'..='Z' => solver.set_door(r, c, ch.to_ascii_lowercase()),
+        _ => solver.set_passage(r, c),
+    })?;
+    solver.is_valid()?;
+    if let Some(solution) = solver.solve(dbg) {
+        println!("{}", solution.rank);
+        if dbg {
+            println!("{:?}", solution.path);
+            println!("{:?}", solution.keys);
+        }
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+/// Solves maze with cell-cost layer read from file given in `costs` (together with the weight of penalties):
+/// reads maze rows from input and prints the cost of the way together with its number of turns and sum of penalties
+fn run_costs(input: &mut impl BufRead, rows: DimType, cols: DimType, (costs, penalty_weight): (&str, u64),
+    start: Option<NodeAdr>, end: Option<NodeAdr>, dbg: bool) -> Result<bool, Error>
+{
+    let (default_start, default_end) = gpw_challenge::default_endpoints(rows, cols);
+    let mut solver = dijkstra_speed::Solver1::new_with_endpoints(rows, cols, start.unwrap_or(default_start), end.unwrap_or(default_end))?;
+    let mut one_way = Vec::new();
+    maze::read_rows_one_way(input, rows, cols, |r, c| solver.set_passage(r, c), |r, c, heading| one_way.push((r, c, heading)))?;
+    for (r, c, heading) in one_way {
+        solver.set_one_way(r, c, heading);
+    }
+    let file = File::open(costs).map_err(|e| Error::InvalidArgument(format!("Cannot open file {}: {}", costs, e)))?;
+    maze::read_cell_costs(&mut BufReader::new(file), 1, rows, cols, |r, c, cost| solver.set_cell_cost(r, c, cost))?;
+    solver.is_valid()?;
+    if let Some(solution) = solver.solve_penalized(penalty_weight, dbg)? {
+        println!("{} {} {}", solution.cost, solution.turns, solution.penalty);
+        if dbg {
+            println!("{:?}", solution.path);
+        }
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+/// Solves maze with maximal and minimal length of straight segments given in `runs`: reads its rows from input
+/// and prints the result, or returns error if there are ways, but none of them satisfies the constraints
+fn run_straight(input: &mut impl BufRead, rows: DimType, cols: DimType, (max_run, min_run): (Option<DimType>, Option<DimType>),
+    start: Option<NodeAdr>, end: Option<NodeAdr>, dbg: bool) -> Result<bool, Error>
+{
+    let (default_start, default_end) = gpw_challenge::default_endpoints(rows, cols);
+    let mut solver = straight_run::Solver1::new_with_endpoints(rows, cols, start.unwrap_or(default_start), end.unwrap_or(default_end))?;
+    if let Some(max_run) = max_run {
+        solver.set_max_run(max_run)?;
+    }
+    if let Some(min_run) = min_run {
+        solver.set_min_run(min_run);
+    }
+    maze::read_rows(input, rows, cols, |r, c| solver.set_passage(r, c))?;
+    solver.is_valid()?;
+    match solver.solve(dbg) {
+        Ok((result, path)) => {
+            println!("{}", result);
+            if dbg {
+                println!("{:?}", path);
+            }
+            Ok(true)
+        },
+        Err(straight_run::NoSolution::Constrained) => Err(Error::RunLimitsUnsatisfied),
+        Err(straight_run::NoSolution::NoWay) => Ok(false),
+    }
+}
+
+fn print_usage() {
+    println!("Usage: gpw_challenge [--dbg] [--start <r,c>] [--end <r,c>] [[--solver <name>] [--portals [--portal-cost <n>]] | --hex] < maze");
+    println!("       gpw_challenge [--dbg] [--start <r,c>] [--end <r,c>] --shortest [--hex] | --break <k> | --keys | --costs <file> [--penalty-weight <n>] | [--max-run <n>] [--min-run <m>] < maze");
+    println!("       gpw_challenge [--dbg] [--start <level,r,c>] [--end <level,r,c>] < layered maze");
+    println!("       gpw_challenge <other option> < numbers");
+    println!();
+    println!("Finds the way with the fewest number of turns thru the maze read from stdin.");
+    println!("With any other option converts input lines from binary to decimal notation.");
+    println!();
+    println!("Options:");
+    println!("  --dbg            print also the solution path");
+    println!("  --shortest       choose the shortest of the ways with the fewest turns, print also its length");
+    println!("  --solver <name>  algorithm to use: {} (default: {})", SOLVER_NAMES.join(", "), SOLVER_NAMES[0]);
+    println!("  --hex            maze on hex grid: odd rows are shifted right by half of the node");
+    println!("  --portals        read portals declared after the maze rows (solvers speed and mem only)");
+    println!("  --portal-cost <n>");
+    println!("                   number of turns which the move thru portal counts as (default: 0)");
+    println!("  --break <k>      allow to break thru up to k walls, with --dbg print also the broken walls");
+    println!("  --keys           maze with keys (a-z) and doors (A-Z) opened by them, with --dbg print also the keys pickup order");
+    println!("  --costs <file>   cell costs grid: . free, 1-9 penalty, X forbidden; print cost, turns and sum of penalties");
+    println!("  --penalty-weight <n>");
+    println!("                   weight of penalties in the cost of the way with --costs (default: 1)");
+    println!("  --max-run <n>    limit straight segments of the way to at most n moves (U-turns are not allowed then)");
+    println!("  --min-run <m>    require at least m moves straight after every turn before the next one (U-turns are not allowed then)");
+    println!("  --start <r,c>    start point (row and column counted from 0), default: 1,0");
+    println!("  --end <r,c>      end point, default: rightmost node in the second last row");
+    println!("  --help           print this help");
+    println!();
+    println!("Besides 0 and 1, maze may contain one-way cells ^, >, v and <, which may be left only in the arrow direction");
+    println!("(solvers speed and mem only).");
+    println!("With --portals, lines r1,c1,r2,c2 after the maze rows declare portals between given nodes.");
+    println!("Options --shortest, --break, --keys, --costs and --max-run/--min-run exclude each other, and options which");
+    println!("would be ignored (e.g. --solver with any of them) are rejected.");
+    println!("Layered maze with header X,Y,Z has Z levels of Y rows each, moves up and down between levels are allowed.");
+    println!("Its start and end points are given as <level,r,c>, default: 0,1,0 and the same as above at the last level.");
+    println!();
+    println!("Exit status: 0 if there is result, 255 (-1) if there is no result, 254 (-2) if data are incorrect,");
+    println!("253 (-3) if there are ways, but none of them satisfies --max-run and --min-run.");
+}
+
+fn main() {
+    match run() {
+        Ok(true) => {},
+        // Data are correct, but there is no result
+        Ok(false) => process::exit(-1),
+        Err(e) => {
+            eprintln!("{}", e);
+            if let Error::UnknownSolver(_) = e {
+                eprintln!("Option --solver requires one of: {}", SOLVER_NAMES.join(", "));
+            }
+            process::exit(e.exit_code());
+        }
+    }
+}
+
+/// Options given in the command line
+#[derive(Default)]
+struct Args {
+    dbg: bool,
+    shortest: bool,
+    hex: bool,
+    converter: bool,
+    solver: Option<String>,
+    start: Option<Vec<DimType>>,
+    end: Option<Vec<DimType>>,
+    portals: bool,
+    portal_cost: Option<DimType>,
+    break_walls: Option<usize>,
+    keys: bool,
+    costs: Option<String>,
+    penalty_weight: Option<u64>,
+    max_run: Option<DimType>,
+    min_run: Option<DimType>,
+}
+
+/// What the program searches for, chosen by options and by the maze header
+enum Mode<'a> {
+    /// The way with the fewest number of turns, as in the challenge
+    Turns,
+    Shortest,
+    Layered(DimType),
+    Break(usize),
+    Keys,
+    Costs(&'a str),
+    Runs,
+}
+
+/// Chooses the mode, rejecting options which exclude each other or would be ignored in the chosen mode
+fn select_mode(args: &Args, levels: Option<DimType>) -> Result<Mode<'_>, Error> {
+    let err = |msg: String| Err(Error::InvalidArgument(msg));
+    let mut modes = Vec::new();
+    if args.shortest { modes.push(("--shortest", Mode::Shortest)); }
+    if let Some(k) = args.break_walls { modes.push(("--break", Mode::Break(k))); }
+    if args.keys { modes.push(("--keys", Mode::Keys)); }
+    if let Some(costs) = &args.costs { modes.push(("--costs", Mode::Costs(costs))); }
+    if args.max_run.is_some() {
+        modes.push(("--max-run", Mode::Runs));
+    } else if args.min_run.is_some() {
+        modes.push(("--min-run", Mode::Runs));
+    }
+    if modes.len() > 1 {
+        return err(format!("Options {} and {} cannot be used together", modes[0].0, modes[1].0));
+    }
+    let (option, mode) = modes.pop().unwrap_or(("", Mode::Turns));
+    let (context, mode) = match levels {
+        Some(_) if !option.is_empty() => return err(format!("Option {} is not supported for layered maze", option)),
+        Some(levels) => ("for layered maze".to_string(), Mode::Layered(levels)),
+        None => (format!("with {}", option), mode),
+    };
+    let unsupported = |option: &str| err(format!("Option {} is not supported {}", option, context));
+
+    // Only the challenge mode and --shortest have solvers on hex grid
+    if args.hex && !matches!(mode, Mode::Turns | Mode::Shortest) { return unsupported("--hex"); }
+    // Other modes use their own solvers
+    if args.solver.is_some() {
+        if !matches!(mode, Mode::Turns) { return unsupported("--solver"); }
+        if args.hex { return err("Options --solver and --hex cannot be used together".to_string()); }
+    }
+    if args.portals {
+        if !matches!(mode, Mode::Turns) { return unsupported("--portals"); }
+        let solver = args.solver.as_deref().unwrap_or(SOLVER_NAMES[0]);
+        if args.hex || (solver != "speed" && solver != "mem") {
+            return err("Option --portals is supported only by solvers speed and mem".to_string());
+        }
+    }
+    if args.portal_cost.is_some() && !args.portals { return err("Option --portal-cost requires --portals".to_string()); }
+    if args.penalty_weight.is_some() && args.costs.is_none() { return err("Option --penalty-weight requires --costs".to_string()); }
+    Ok(mode)
+}
+
+/// Returns false if there is no solution
+fn run() -> Result<bool, Error> {
+    let args: Vec<String> = env::args().collect();
+    let mut opt = Args::default();
+    let mut arg_iter = args.iter().skip(1);
+    while let Some(arg) = arg_iter.next() {
+        match arg.as_str() {
+            "--help" => {
+                print_usage();
+                return Ok(true);
+            },
+            "--dbg" => opt.dbg = true,
+            "--shortest" => opt.shortest = true,
+            "--hex" => opt.hex = true,
+            "--solver" => {
+                match arg_iter.next() {
+                    Some(name) if SOLVER_NAMES.contains(&name.as_str()) => opt.solver = Some(name.clone()),
+                    Some(name) => return Err(Error::UnknownSolver(name.clone())),
+                    None => return Err(Error::InvalidArgument(format!("Option {} requires one of: {}", arg, SOLVER_NAMES.join(", ")))),
+                }
+            },
+            "--start" => opt.start = Some(parse_node(arg, arg_iter.next())?),
+            "--end" => opt.end = Some(parse_node(arg, arg_iter.next())?),
+            "--keys" => opt.keys = true,
+            "--portals" => opt.portals = true,
+            "--break" => {
+                match arg_iter.next().map(|n| n.parse::<usize>()) {
+                    Some(Ok(k)) => opt.break_walls = Some(k),
+                    _ => return Err(Error::InvalidArgument(format!("Option {} requires number of walls", arg))),
+                }
+            },
+            "--costs" => {
+                match arg_iter.next() {
+                    Some(file) => opt.costs = Some(file.clone()),
+                    None => return Err(Error::InvalidArgument(format!("Option {} requires file name", arg))),
+                }
+            },
+            "--penalty-weight" => {
+                match arg_iter.next().map(|n| n.parse::<u64>()) {
+                    Some(Ok(weight)) => opt.penalty_weight = Some(weight),
+                    _ => return Err(Error::InvalidArgument(format!("Option {} requires number", arg))),
+                }
+            },
+            "--max-run" => {
+                match arg_iter.next().map(|n| n.parse::<DimType>()) {
+                    Some(Ok(cells)) => opt.max_run = Some(cells),
+                    _ => return Err(Error::InvalidArgument(format!("Option {} requires number of cells", arg))),
+                }
+            },
+            "--min-run" => {
+                match arg_iter.next().map(|n| n.parse::<DimType>()) {
+                    Some(Ok(cells)) => opt.min_run = Some(cells),
+                    _ => return Err(Error::InvalidArgument(format!("Option {} requires number of cells", arg))),
+                }
+            },
+            "--portal-cost" => {
+                match arg_iter.next().map(|n| n.parse::<DimType>()) {
+                    Some(Ok(turns)) => opt.portal_cost = Some(turns),
+                    _ => return Err(Error::InvalidArgument(format!("Option {} requires number of turns", arg))),
+                }
+            },
+            _ => opt.converter = true,
+        }
     }
+
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
     #[allow(non_snake_case)]
-    let (X, Y) = (XY[0]?, XY[1]?);
+    let (Z, Y, X) = maze::read_header3(&mut input)?;
 
-    if arg_converter {
+    if opt.converter {
         use num::bigint::BigUint;
         use num_traits::Num;
 
-        buf.clear();
+        let mut buf = String::new();
         let mut line_no = 2;
-        while let Ok(n) = stdin.read_line(&mut buf) {
+        while let Ok(n) = input.read_line(&mut buf) {
             if n > 0 {
                 match BigUint::from_str_radix(buf.trim_end(), 2) {
                     Ok(num) => {
@@ -57,118 +418,64 @@ fn main() -> Result<(), isize> {
             buf.clear();
             line_no += 1;
         }
-        Ok(())
-    } else {
-        let mut solver = match solver_factory(Y, X) {
-            Ok(solver) => solver,
-            Err(msg) => {
-                eprintln!("Could not create solver: {}", msg);
-                return Err(-2);
-            }
-        };
-
-        for line_no in 0..Y {
-            buf.clear();
-            if let Err(e) = stdin.read_line(&mut buf) {
-                eprintln!("Input line {}: {}", line_no + 2, e);
-                return Err(-2);
-            }
-            let line = buf.trim_end();
-            if line.len() != X as usize {
-                eprintln!("Input line {}: line should have {} characters, but has {}", line_no + 2, X, line.len());
-                return Err(-2);
-            }
-            let mut ch_no:DimType = 0;
-            for ch in line.chars() {
-                match ch {
-                    '0' => {},
-                    '1' => {
-                        solver.set_passage(line_no, ch_no);
-                    },
-                    _ => {
-                        eprintln!("Input line {}: invalid character: {}", line_no + 2, ch);
-                        return Err(-2);
-                    }
-                }
-                ch_no += 1;
-            }
-
-        }
-        if !solver.is_valid() {
-            return Err(-1);
-        }
-
-        if let Some((result, path)) = solver.solve(arg_dbg) {
-            println!("{}", result);
-            if arg_dbg {
-                println!("{:?}", path);
-            }
-            Ok(())
-        } else {
-            Err(-1)
-        }
+        return Ok(true);
     }
-}
 
-/// To store dimension of puzzle
-/// must be castable to usize
-type DimType = u16;
+    let mode = select_mode(&opt, Z)?;
+    if let Mode::Layered(levels) = mode {
+        return run_layered(&mut input, levels, Y, X, opt.start, opt.end, opt.dbg);
+    }
+    check_node("--start", &opt.start, 2)?;
+    check_node("--end", &opt.end, 2)?;
+    let (start, end) = (opt.start.as_ref().map(|n| (n[0], n[1])), opt.end.as_ref().map(|n| (n[0], n[1])));
+    match mode {
+        Mode::Shortest => return run_shortest(&mut input, Y, X, opt.hex, start, end, opt.dbg),
+        Mode::Break(k) => return run_breaking(&mut input, Y, X, k, start, end, opt.dbg),
+        Mode::Keys => return run_keys(&mut input, Y, X, start, end, opt.dbg),
+        Mode::Costs(costs) => return run_costs(&mut input, Y, X, (costs, opt.penalty_weight.unwrap_or(1)), start, end, opt.dbg),
+        Mode::Runs => return run_straight(&mut input, Y, X, (opt.max_run, opt.min_run), start, end, opt.dbg),
+        Mode::Turns | Mode::Layered(_) => {},
+    }
 
-type NodeAdr = (DimType, DimType);
+    let mut solver = solver_factory(opt.solver.as_deref().unwrap_or(SOLVER_NAMES[0]), opt.hex, Y, X, start, end)?;
+    let mut one_way = Vec::new();
+    maze::read_rows_one_way(&mut input, Y, X, |r, c| solver.set_passage(r, c), |r, c, heading| one_way.push((r, c, heading)))?;
+    for (r, c, heading) in one_way.iter() {
+        solver.set_one_way(*r, *c, *heading)?;
+    }
+    solver.is_valid()?;
+    let portals = if opt.portals { maze::read_portals(&mut input, Y as usize + 2, Y, X)? } else { Vec::new() };
+    for (a, b) in portals {
+        solver.add_portal(a, b)?;
+    }
+    if let Some(turns) = opt.portal_cost {
+        solver.set_portal_cost(turns)?;
+    }
 
-// Sized is required in order to provide default implementation of solve_and_drop
-pub trait Solver:Sized {
-    fn set_passage(&mut self, r: DimType, c:DimType);
-    fn is_valid(&self) -> bool;
-    fn solve(&self, with_path: bool) -> Option<(DimType, VecDeque<NodeAdr>)>;
-    fn solve_and_drop(self, with_path: bool) -> Option<(DimType, VecDeque<NodeAdr>)> {
-        self.solve(with_path)
+    if let Some((result, path)) = solver.solve(opt.dbg) {
+        println!("{}", result);
+        if opt.dbg {
+            println!("{:?}", path);
+        }
+        Ok(true)
+    } else {
+        Ok(false)
     }
 }
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
-/// Tests
-/// 
+// Tests
+//
 
 #[cfg(test)]
 mod tests {
-    use rand::Rng;
-    use ndarray::{Array, ArrayView2};
-    use ndarray_rand::RandomExt;
-    use ndarray_rand::rand_distr::Uniform;
     use super::*;
-    
-    pub fn init_from_rand_array<A:PartialOrd>(solv:&mut impl Solver, arr:&ArrayView2<A>, frac:A) {
-        let dim = arr.shape();
-        for (c, v) in arr.indexed_iter() {
-            if *v <= frac {
-                solv.set_passage(c.0 as DimType, c.1 as DimType);
-            }
-        }
-        solv.set_passage(1, 0);
-        solv.set_passage(dim[0] as DimType - 2, dim[1] as DimType - 1);
-        assert!(solv.is_valid());
-    }
 
     #[test]
-    fn size_constraints() {
-        assert!(DimType::MAX >= 10_000);
-        assert!((DimType::MAX as usize)^2 <= usize::MAX);
-        assert_eq!(dijkstra_speed::Solver1::new_rc(10_000, 10_000).is_ok(), true);
-        assert_eq!(dijkstra_mem::Solver1::new_rc(10_000, 10_000).is_ok(), true);
+    fn costs_with_portals() {
+        let args = Args { costs: Some("costs".to_string()), portals: true, ..Default::default() };
+        assert!(matches!(select_mode(&args, None), Err(Error::InvalidArgument(_))));
+        let args = Args { costs: Some("costs".to_string()), ..Default::default() };
+        assert!(matches!(select_mode(&args, None), Ok(Mode::Costs("costs"))));
     }
-    
-    #[test]
-    fn cross_check() {
-        let mut rng = rand::thread_rng();
-        let r_dim = rng.gen_range(5, 20);
-        let c_dim = rng.gen_range(5, 20);
-        let arr = Array::random((r_dim, c_dim), Uniform::new_inclusive(0, 100));
-        let mut solv1 = dijkstra_speed::Solver1::new_rc(r_dim as DimType, c_dim as DimType).unwrap();
-        let mut solv2 = dijkstra_mem::Solver1::new_rc(r_dim as DimType, c_dim as DimType).unwrap();
-        init_from_rand_array(&mut solv1, &arr.view(), 75);
-        init_from_rand_array(&mut solv2, &arr.view(), 75);
-        assert_eq!(solv1.solve(false), solv2.solve(false));
-    }
-    
 }
@@ -0,0 +1,331 @@
+//! Maze grid and parsing of the input format:
+//! first line `X,Y` (number of columns and rows), followed by Y lines of X characters,
+//! where `0` is a wall and `1` is a passage.
+//! Layered (3-D) maze has header `X,Y,Z` (Z is number of levels), followed by Z blocks of Y lines each.
+
+use std::io::BufRead;
+use std::str::FromStr;
+
+use super::*;
+
+/// Maze grid kept independently from any solver
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Maze {
+    data: Vec<u8>,
+    r_dim: DimType,
+    c_dim: DimType,
+}
+
+impl Maze {
+
+    /// Creates maze with given number of rows and columns, filled with walls
+    pub fn new_rc(r: DimType, c:DimType) -> Self {
+        Maze {
+            data: vec![0; r as usize * c as usize],
+            r_dim:r,
+            c_dim:c,
+        }
+    }
+
+    /// Reads whole maze (header and rows)
+    pub fn read<R: BufRead>(input: &mut R) -> Result<Self, Error> {
+        let (r, c) = read_header(input)?;
+        let mut maze = Maze::new_rc(r, c);
+        read_rows(input, r, c, |row, col| maze.set_passage(row, col))?;
+        Ok(maze)
+    }
+
+    pub fn rows(&self) -> DimType {
+        self.r_dim
+    }
+
+    pub fn cols(&self) -> DimType {
+        self.c_dim
+    }
+
+    #[inline]
+    fn idx(&self, r: DimType, c:DimType) -> usize {
+        r as usize * self.c_dim as usize + c as usize
+    }
+
+    pub fn set_passage(&mut self, r: DimType, c:DimType) {
+        let idx = self.idx(r, c);
+        self.data[idx] = 1;
+    }
+
+    pub fn is_passage(&self, r: DimType, c:DimType) -> bool {
+        self.data[self.idx(r, c)] == 1
+    }
+
+    /// Checks that start and end points lie within the maze and are passages
+    pub fn check_endpoints(&self, start: NodeAdr, end: NodeAdr) -> Result<(), Error> {
+        check_endpoints(self.r_dim, self.c_dim, start, end)?;
+        if !self.is_passage(start.0, start.1) { return Err(Error::StartBlocked(start)); }
+        if !self.is_passage(end.0, end.1) { return Err(Error::EndBlocked(end)); }
+        Ok(())
+    }
+
+    /// Iterates over addresses of all passages, row by row
+    pub fn passages(&self) -> impl Iterator<Item = NodeAdr> + '_ {
+        let c_dim = self.c_dim as usize;
+        self.data.iter().enumerate()
+            .filter(|(_, v)| **v == 1)
+            .map(move |(idx, _)| ((idx / c_dim) as DimType, (idx % c_dim) as DimType))
+    }
+
+    /// Sets all passages of this maze in the solver
+    pub fn load_into(&self, solver: &mut impl Solver) {
+        for (r, c) in self.passages() {
+            solver.set_passage(r, c);
+        }
+    }
+}
+
+impl FromStr for Maze {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Maze::read(&mut s.as_bytes())
+    }
+}
+
+/// Reads the first line `X,Y` of input and returns maze dimensions as (rows, columns)
+pub fn read_header<R: BufRead>(input: &mut R) -> Result<(DimType, DimType), Error> {
+    let (_, r, c) = read_header3(input)?;
+    Ok((r, c))
+}
+
+/// Reads the first line `X,Y` or `X,Y,Z` of input and returns maze dimensions as (levels, rows, columns),
+/// where number of levels is `None` for 2-D maze
+pub fn read_header3<R: BufRead>(input: &mut R) -> Result<(Option<DimType>, DimType, DimType), Error> {
+    let mut buf = String::new();
+    input.read_line(&mut buf).map_err(|e| Error::Io { line:1, source:e })?;
+    #[allow(non_snake_case)]
+    let XYZ = buf.split(',')
+        .map(|s| DimType::from_str_radix(s.trim(), 10))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| Error::InvalidHeader(format!("parsing error: {}", e)))?;
+    if XYZ.len() != 2 && XYZ.len() != 3 {
+        return Err(Error::InvalidHeader("should have 2 or 3 comma separated elements".to_string()));
+    }
+    Ok((XYZ.get(2).copied(), XYZ[1], XYZ[0]))
+}
+
+/// Reads `r` lines of `c` characters each and calls `set_passage` for every passage found
+pub fn read_rows<R: BufRead>(input: &mut R, r: DimType, c: DimType, mut set_passage: impl FnMut(DimType, DimType)) -> Result<(), Error> {
+    read_rows_from(input, 2, r, c, |row, col, ch| passage_cell(ch, || set_passage(row, col)))
+}
+
+/// The same as `read_rows`, but accepts also one-way cells `^`, `>`, `v` and `<` (passages which may be left only
+/// in the direction of the arrow) and calls `set_one_way` with the heading of the arrow for them
+pub fn read_rows_one_way<R: BufRead>(input: &mut R, r: DimType, c: DimType,
+    mut set_passage: impl FnMut(DimType, DimType), mut set_one_way: impl FnMut(DimType, DimType, Heading)) -> Result<(), Error>
+{
+    read_rows_from(input, 2, r, c, |row, col, ch| {
+        let heading = match ch {
+            '^' => Heading::Up,
+            '>' => Heading::Right,
+            'v' => Heading::Down,
+            '<' => Heading::Left,
+            _ => return passage_cell(ch, || set_passage(row, col)),
+        };
+        set_one_way(row, col, heading);
+        true
+    })
+}
+
+/// The same as `read_rows`, but accepts also keys (lowercase ASCII letters) and doors (uppercase ones)
+/// and calls `set_cell` with the character for every passage, key and door
+pub fn read_rows_keys<R: BufRead>(input: &mut R, r: DimType, c: DimType, mut set_cell: impl FnMut(DimType, DimType, char)) -> Result<(), Error> {
+    read_rows_from(input, 2, r, c, |row, col, ch| {
+        if !ch.is_ascii_alphabetic() { return passage_cell(ch, || set_cell(row, col, ch)); }
+        set_cell(row, col, ch);
+        true
+    })
+}
+
+/// Reads cell-cost layer of the maze of given dimensions: `r` lines of `c` characters each, starting at line `first_line`
+/// of input (counted from 1), where `.` is a free cell, digits `1`-`9` are penalties and `X` is a forbidden cell.
+/// Calls `set_cost` for every cell which is not free.
+pub fn read_cell_costs<R: BufRead>(input: &mut R, first_line: usize, r: DimType, c: DimType,
+    mut set_cost: impl FnMut(DimType, DimType, CellCost)) -> Result<(), Error>
+{
+    read_rows_from(input, first_line, r, c, |row, col, ch| {
+        match ch {
+            '.' => {},
+            '1'..='9' => set_cost(row, col, CellCost::Penalty(ch as u32 - '0' as u32)),
+            'X' => set_cost(row, col, CellCost::Forbidden),
+            _ => return false,
+        }
+        true
+    })
+}
+
+/// Reads `z` blocks of `r` lines of `c` characters each and calls `set_passage` for every passage found
+pub fn read_levels<R: BufRead>(input: &mut R, z: DimType, r: DimType, c: DimType, mut set_passage: impl FnMut(DimType, DimType, DimType)) -> Result<(), Error> {
+    for level in 0..z {
+        read_rows_from(input, 2 + level as usize * r as usize, r, c, |row, col, ch| passage_cell(ch, || set_passage(level, row, col)))?;
+    }
+    Ok(())
+}
+
+/// Reads portals declared in lines following the maze rows (starting at line `first_line` of input, counted from 1),
+/// till the end of input. Every line links two nodes of the maze of given dimensions: `r1,c1,r2,c2`.
+/// Empty lines are skipped.
+pub fn read_portals<R: BufRead>(input: &mut R, first_line: usize, r: DimType, c: DimType) -> Result<Vec<(NodeAdr, NodeAdr)>, Error> {
+    let mut portals = Vec::new();
+    let mut buf = String::new();
+    for line_no in first_line.. {
+        buf.clear();
+        if input.read_line(&mut buf).map_err(|e| Error::Io { line:line_no, source:e })? == 0 { break; }
+        let line = buf.trim();
+        if line.is_empty() { continue; }
+        let err = || Error::InvalidPortal { line:line_no, text:line.to_string() };
+        let rc = line.split(',')
+            .map(|s| s.trim().parse::<DimType>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| err())?;
+        if rc.len() != 4 || rc[0] >= r || rc[1] >= c || rc[2] >= r || rc[3] >= c { return Err(err()); }
+        portals.push(((rc[0], rc[1]), (rc[2], rc[3])));
+    }
+    Ok(portals)
+}
+
+/// Handles cell of `0` / `1` maze: calls `set_passage` for passage. Returns false for invalid character.
+fn passage_cell(ch: char, set_passage: impl FnOnce()) -> bool {
+    match ch {
+        '0' => true,
+        '1' => {
+            set_passage();
+            true
+        },
+        _ => false,
+    }
+}
+
+/// Reads `r` lines starting at line `first_line` of input (counted from 1) and calls `cell` for every character,
+/// which returns false if the character is invalid
+fn read_rows_from<R: BufRead>(input: &mut R, first_line: usize, r: DimType, c: DimType, mut cell: impl FnMut(DimType, DimType, char) -> bool) -> Result<(), Error> {
+    let mut buf = String::new();
+    for line_no in 0..r {
+        let line_no_in = line_no as usize + first_line;
+        buf.clear();
+        input.read_line(&mut buf).map_err(|e| Error::Io { line:line_no_in, source:e })?;
+        let line = buf.trim_end();
+        if line.len() != c as usize {
+            return Err(Error::WrongLineLength { line:line_no_in, expected:c, actual:line.len() });
+        }
+        for (ch_no, ch) in (0..).zip(line.chars()) {
+            if !cell(line_no, ch_no, ch) {
+                return Err(Error::InvalidCharacter { line:line_no_in, column:ch_no as usize + 1, ch });
+            }
+        }
+    }
+    Ok(())
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read() {
+        let maze: Maze = "4,3\n0000\n1101\n0111\n".parse().unwrap();
+        assert_eq!(maze.rows(), 3);
+        assert_eq!(maze.cols(), 4);
+        assert!(maze.is_passage(1, 0));
+        assert!(!maze.is_passage(1, 2));
+        assert_eq!(maze.passages().collect::<Vec<_>>(), vec![(1, 0), (1, 1), (1, 3), (2, 1), (2, 2), (2, 3)]);
+    }
+
+    #[test]
+    fn read_errors() {
+        assert!(matches!("4\n".parse::<Maze>(), Err(Error::InvalidHeader(_))));
+        assert!(matches!("4,x\n".parse::<Maze>(), Err(Error::InvalidHeader(_))));
+        assert_eq!(
+            "4,3,2,9\n".parse::<Maze>().unwrap_err().to_string(),
+            "Input line 1: should have 2 or 3 comma separated elements"
+        );
+        assert!(matches!("4,2\n0000\n111\n".parse::<Maze>(), Err(Error::WrongLineLength { line:3, expected:4, actual:3 })));
+        assert!(matches!("4,2\n0000\n1121\n".parse::<Maze>(), Err(Error::InvalidCharacter { line:3, column:3, ch:'2' })));
+        assert_eq!(
+            "4,2\n0000\n1121\n".parse::<Maze>().unwrap_err().to_string(),
+            "Input line 3, column 3: invalid character: 2"
+        );
+    }
+
+    #[test]
+    fn load_into() {
+        let maze: Maze = "4,3\n0000\n1101\n0111\n".parse().unwrap();
+        let mut solv = dijkstra_speed::Solver1::new_rc(maze.rows(), maze.cols()).unwrap();
+        maze.load_into(&mut solv);
+        assert!(solv.is_valid().is_ok());
+        assert_eq!(solv.solve(false), Some((3, VecDeque::new())));
+    }
+
+    #[test]
+    fn read_rows_one_way() {
+        let mut passages = Vec::new();
+        let mut one_way = Vec::new();
+        super::read_rows_one_way(&mut "1>0\n^v<\n".as_bytes(), 2, 3, |r, c| passages.push((r, c)), |r, c, h| one_way.push((r, c, h))).unwrap();
+        assert_eq!(passages, vec![(0, 0)]);
+        assert_eq!(one_way, vec![(0, 1, Heading::Right), (1, 0, Heading::Up), (1, 1, Heading::Down), (1, 2, Heading::Left)]);
+        assert!(matches!(super::read_rows_one_way(&mut "1>x\n".as_bytes(), 1, 3, |_, _| {}, |_, _, _| {}),
+            Err(Error::InvalidCharacter { line:2, column:3, ch:'x' })));
+        assert!(matches!("3,2\n000\n1>1\n".parse::<Maze>(), Err(Error::InvalidCharacter { line:3, column:2, ch:'>' })));
+    }
+
+    #[test]
+    fn read_rows_keys() {
+        let mut cells = Vec::new();
+        super::read_rows_keys(&mut "1a0\n0B1\n".as_bytes(), 2, 3, |r, c, ch| cells.push((r, c, ch))).unwrap();
+        assert_eq!(cells, vec![(0, 0, '1'), (0, 1, 'a'), (1, 1, 'B'), (1, 2, '1')]);
+        assert!(matches!(super::read_rows_keys(&mut "1a>\n".as_bytes(), 1, 3, |_, _, _| {}),
+            Err(Error::InvalidCharacter { line:2, column:3, ch:'>' })));
+    }
+
+    #[test]
+    fn read_cell_costs() {
+        let mut costs = Vec::new();
+        super::read_cell_costs(&mut ".3X\n9..\n".as_bytes(), 1, 2, 3, |r, c, cost| costs.push((r, c, cost))).unwrap();
+        assert_eq!(costs, vec![(0, 1, CellCost::Penalty(3)), (0, 2, CellCost::Forbidden), (1, 0, CellCost::Penalty(9))]);
+        assert!(matches!(super::read_cell_costs(&mut "..\n.0\n".as_bytes(), 1, 2, 2, |_, _, _| {}),
+            Err(Error::InvalidCharacter { line:2, column:2, ch:'0' })));
+        assert!(matches!(super::read_cell_costs(&mut "..\n".as_bytes(), 5, 2, 2, |_, _, _| {}),
+            Err(Error::WrongLineLength { line:6, .. })));
+    }
+
+    #[test]
+    fn read_portals() {
+        let mut input = "3,2\n110\n011\n0,0,1,2\n\n1,1,0,1\n".as_bytes();
+        let (r, c) = read_header(&mut input).unwrap();
+        read_rows(&mut input, r, c, |_, _| {}).unwrap();
+        assert_eq!(super::read_portals(&mut input, 4, r, c).unwrap(), vec![((0, 0), (1, 2)), ((1, 1), (0, 1))]);
+        assert!(super::read_portals(&mut "".as_bytes(), 4, r, c).unwrap().is_empty());
+        assert!(matches!(super::read_portals(&mut "0,0,1,2\n0,0,2,0\n".as_bytes(), 4, r, c), Err(Error::InvalidPortal { line:5, .. })));
+        assert_eq!(
+            super::read_portals(&mut "0,0,1\n".as_bytes(), 4, r, c).unwrap_err().to_string(),
+            "Input line 4: invalid portal: 0,0,1"
+        );
+    }
+
+    #[test]
+    fn read_levels() {
+        let mut input = "3,2,2\n000\n110\n011\n010\n".as_bytes();
+        assert_eq!(read_header3(&mut input).unwrap(), (Some(2), 2, 3));
+        let mut solv = layered::Solver1::new_zrc(2, 2, 3).unwrap();
+        let mut passages = Vec::new();
+        super::read_levels(&mut input, 2, 2, 3, |z, r, c| { passages.push((z, r, c)); solv.set_passage(z, r, c); }).unwrap();
+        assert_eq!(passages, vec![(0, 1, 0), (0, 1, 1), (1, 0, 1), (1, 0, 2), (1, 1, 1)]);
+        assert_eq!(solv.solve(false), Some((3, VecDeque::new())));
+        assert_eq!(read_header3(&mut "3,2\n".as_bytes()).unwrap(), (None, 2, 3));
+        assert!(matches!(read_header3(&mut "4,3,2,9\n".as_bytes()), Err(Error::InvalidHeader(_))));
+
+        let mut input = "3,2,2\n000\n110\n010\n01\n".as_bytes();
+        read_header3(&mut input).unwrap();
+        assert!(matches!(super::read_levels(&mut input, 2, 2, 3, |_, _, _| {}), Err(Error::WrongLineLength { line:5, expected:3, actual:2 })));
+    }
+}
@@ -0,0 +1,328 @@
+//! Finding the simplest way with constraints on the length of its straight segments,
+//! e.g. for vehicles which have to turn (or stop) at least every N cells,
+//! or which after a turn have to go at least M cells straight before turning again (turning radius).
+//! Moving back to the previous node is not allowed here, as it would let the mover reset the run in place.
+
+use std::collections::{HashMap, VecDeque};
+
+use super::*;
+use grid::Grid;
+
+/// Reason why `Solver1::solve` did not find the way
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoSolution {
+    /// There is no way from start to end at all (or start or end point is not a passage)
+    NoWay,
+    /// There are ways from start to end, but none of them satisfies the constraints
+    Constrained,
+}
+
+pub struct Solver1 {
+    grid: Grid,
+    max_run: Option<DimType>,
+    min_run: DimType,
+}
+
+/// Search state: node index, heading of the move by which it was entered,
+/// number of moves made with this heading so far (0 at start)
+/// and whether it is the first segment of the way which is still shorter than minimal run
+type State = (usize, Heading, DimType, bool);
+
+impl Solver1 {
+
+    pub fn new_rc(r: DimType, c:DimType) -> Result<Self, Error> {
+        let (start, end) = default_endpoints(r, c);
+        Self::new_with_endpoints(r, c, start, end)
+    }
+
+    /// Creates solver for maze with given start and end points
+    pub fn new_with_endpoints(r: DimType, c:DimType, start: NodeAdr, end: NodeAdr) -> Result<Self, Error> {
+        Ok(Solver1 {
+            grid: Grid::new_with_endpoints(r, c, start, end)?,
+            max_run: None,
+            min_run: 0,
+        })
+    }
+
+    /// Limits the length of every straight segment of the way to at most `cells` moves (by default there is no limit)
+    pub fn set_max_run(&mut self, cells: DimType) -> Result<(), Error> {
+        if cells == 0 { return Err(Error::InvalidArgument("Maximal straight run must be at least 1".to_string())); }
+        self.max_run = Some(cells);
+        Ok(())
+    }
+
+    /// Requires every straight segment of the way which follows a turn to have at least `cells` moves
+    /// before the next turn (by default there is no such requirement).
+    /// The first segment does not follow any turn, and the last one is not followed by a turn (the mover stops at the end),
+    /// so they may be shorter.
+    pub fn set_min_run(&mut self, cells: DimType) {
+        self.min_run = cells;
+    }
+
+    /// Returns index of neighbor node in given direction if it is a passage
+    #[inline]
+    fn neighbor(&self, idx: usize, heading: Heading) -> Option<usize> {
+        let n_idx = self.grid.step(idx, heading)?;
+        if self.grid.data[n_idx] != 0 { Some(n_idx) } else { None }
+    }
+
+    /// Returns run and first segment flag after the move with `next_heading` from state entered with `heading`
+    /// after `run` moves, or `None` if such move is not allowed. Runs are counted only up to the value
+    /// which matters for the constraints, so that the number of states stays small.
+    #[inline]
+    fn next_run(&self, heading: Heading, run: DimType, first: bool, next_heading: Heading) -> Option<(DimType, bool)> {
+        if run > 0 && next_heading == heading.opposite() { return None; }
+        let (next_run, first) = if next_heading == heading || run == 0 {
+            (run + 1, run == 0 || first)
+        } else {
+            if !first && run < self.min_run { return None; }
+            (1, false)
+        };
+        if let Some(max_run) = self.max_run {
+            if next_run > max_run { return None; }
+        }
+        let next_run = next_run.min(self.max_run.unwrap_or(1).max(self.min_run));
+        Some((next_run, first && next_run < self.min_run))
+    }
+
+    pub fn set_passage(&mut self, r: DimType, c: DimType) {
+        self.grid.set_cell(r, c, 1);
+    }
+
+    /// Checks that both start and end points are passages
+    pub fn is_valid(&self) -> Result<(), Error> {
+        self.grid.is_valid()
+    }
+
+    /// Finds the way with the fewest number of turns which satisfies the constraints.
+    /// Returns number of turns and the way (empty if not requested), or the reason why there is no such way.
+    ///
+    /// Search runs on (node, heading, run) states with 0-1 BFS, like `keys::Solver1::solve`.
+    pub fn solve(&self, with_path: bool) -> Result<(DimType, VecDeque<NodeAdr>), NoSolution> {
+        if self.is_valid().is_err() { return Err(NoSolution::NoWay); }
+        let mut states = HashMap::<State, (DimType, Option<State>)>::new();
+        let mut todo = VecDeque::new();
+        let start = self.grid.idx(self.grid.start);
+        // First move from start node in any direction is not a turn
+        for heading in Heading::ALL.iter() {
+            let state = (start, *heading, 0, false);
+            states.insert(state, (0, None));
+            todo.push_back((state, 0));
+        }
+        let end = self.grid.idx(self.grid.end);
+        let mut end_state = None;
+        while let Some((state, rank)) = todo.pop_front() {
+            if states[&state].0 < rank { continue; }
+            let (idx, heading, run, first) = state;
+            if idx == end {
+                end_state = Some(state);
+                break;
+            }
+            for next_heading in Heading::ALL.iter() {
+                let n_idx = match self.neighbor(idx, *next_heading) {
+                    Some(n_idx) => n_idx,
+                    None => continue,
+                };
+                let (next_run, next_first) = match self.next_run(heading, run, first, *next_heading) {
+                    Some(next_run) => next_run,
+                    None => continue,
+                };
+                let next = (n_idx, *next_heading, next_run, next_first);
+                let next_rank = if *next_heading == heading { rank } else { rank + 1 };
+                let better = match states.get(&next) {
+                    Some((old_rank, _)) => next_rank < *old_rank,
+                    None => true,
+                };
+                if better {
+                    states.insert(next, (next_rank, Some(state)));
+                    if next_rank == rank { todo.push_front((next, next_rank)); } else { todo.push_back((next, next_rank)); }
+                }
+            }
+        }
+
+        let end_state = match end_state {
+            Some(end_state) => end_state,
+            None => return Err(if self.is_reachable() { NoSolution::Constrained } else { NoSolution::NoWay }),
+        };
+        let mut path = VecDeque::new();
+        if with_path {
+            let mut state = Some(end_state);
+            while let Some(cur_state) = state {
+                path.push_front(self.grid.rc(cur_state.0));
+                state = states[&cur_state].1;
+            }
+        }
+        Ok((states[&end_state].0, path))
+    }
+
+    /// Checks whether end point may be reached from start point at all, ignoring the constraints
+    fn is_reachable(&self) -> bool {
+        let mut visited = vec![false; self.grid.data.len()];
+        let mut todo = vec![self.grid.idx(self.grid.start)];
+        visited[todo[0]] = true;
+        while let Some(idx) = todo.pop() {
+            if idx == self.grid.idx(self.grid.end) { return true; }
+            for heading in Heading::ALL.iter() {
+                if let Some(n_idx) = self.neighbor(idx, *heading) {
+                    if !visited[n_idx] {
+                        visited[n_idx] = true;
+                        todo.push(n_idx);
+                    }
+                }
+            }
+        }
+        false
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{rand_maze, reference_rank, count_turns};
+
+    /// Lengths of straight segments of the way
+    fn runs(path: &VecDeque<NodeAdr>) -> Vec<usize> {
+        let mut runs = Vec::new();
+        let mut last_move = None;
+        for i in 1..path.len() {
+            let node_move = (path[i].0 as isize - path[i - 1].0 as isize, path[i].1 as isize - path[i - 1].1 as isize);
+            if last_move == Some(node_move) { *runs.last_mut().unwrap() += 1; } else { runs.push(1); }
+            last_move = Some(node_move);
+        }
+        runs
+    }
+
+    #[test]
+    fn max_run() {
+        // Straight corridor with the bypass below its middle
+        let mut solv = Solver1::new_with_endpoints(4, 7, (1, 0), (1, 6)).unwrap();
+        for c in 0..7 {
+            solv.set_passage(1, c);
+        }
+        assert_eq!(solv.solve(false), Ok((0, VecDeque::new())));
+        solv.set_max_run(6).unwrap();
+        assert_eq!(solv.solve(false), Ok((0, VecDeque::new())));
+        solv.set_max_run(3).unwrap();
+        assert_eq!(solv.solve(false), Err(NoSolution::Constrained));
+        solv.set_passage(2, 3);
+        solv.set_passage(2, 4);
+        assert_eq!(
+            solv.solve(true),
+            Ok((4, vec![(1, 0), (1, 1), (1, 2), (1, 3), (2, 3), (2, 4), (1, 4), (1, 5), (1, 6)].into_iter().collect()))
+        );
+        solv.set_max_run(2).unwrap();
+        assert_eq!(solv.solve(false), Err(NoSolution::Constrained));
+        assert!(matches!(solv.set_max_run(0), Err(Error::InvalidArgument(_))));
+
+        let mut solv = Solver1::new_with_endpoints(4, 7, (1, 0), (1, 6)).unwrap();
+        for c in [0, 1, 2, 4, 5, 6].iter() {
+            solv.set_passage(1, *c);
+        }
+        assert_eq!(solv.solve(false), Err(NoSolution::NoWay));
+        solv.set_max_run(3).unwrap();
+        assert_eq!(solv.solve(false), Err(NoSolution::NoWay));
+        assert_eq!(Solver1::new_rc(4, 4).unwrap().solve(false), Err(NoSolution::NoWay));
+    }
+
+    #[test]
+    fn min_run() {
+        // Both simplest ways go down 2 cells between turns, the other one has all segments between turns of 3 cells or more
+        let mut solv = Solver1::new_with_endpoints(8, 9, (1, 0), (3, 8)).unwrap();
+        for node in [(2, 1), (2, 5), (4, 5), (5, 5), (6, 5), (5, 8), (4, 8)].iter() {
+            solv.set_passage(node.0, node.1);
+        }
+        for c in 0..6 {
+            solv.set_passage(1, c);
+        }
+        for c in 1..9 {
+            solv.set_passage(3, c);
+            if c > 5 { solv.set_passage(6, c); }
+        }
+        assert_eq!(solv.solve(false).map(|s| s.0), Ok(2));
+        // The first segment of the way thru (2,1) is shorter, but it does not follow a turn
+        solv.set_min_run(2);
+        assert_eq!(solv.solve(false).map(|s| s.0), Ok(2));
+        solv.set_min_run(3);
+        assert_eq!(
+            solv.solve(true),
+            Ok((3, (0..6).map(|c| (1, c)).chain((2..7).map(|r| (r, 5))).chain((6..9).map(|c| (6, c))).chain((3..6).rev().map(|r| (r, 8))).collect()))
+        );
+        // The last segment may be shorter, as the mover stops at the end
+        solv.set_min_run(5);
+        assert_eq!(solv.solve(false), Err(NoSolution::Constrained));
+        solv.set_min_run(4);
+        assert_eq!(solv.solve(false), Err(NoSolution::Constrained));
+        let mut solv_end = Solver1::new_with_endpoints(8, 9, (1, 0), (3, 2)).unwrap();
+        for node in [(1, 0), (1, 1), (2, 1), (3, 1), (3, 2)].iter() {
+            solv_end.set_passage(node.0, node.1);
+        }
+        solv_end.set_min_run(2);
+        assert_eq!(solv_end.solve(false), Ok((2, VecDeque::new())));
+        solv_end.set_min_run(3);
+        assert_eq!(solv_end.solve(false), Err(NoSolution::Constrained));
+
+        // Both constraints at once: segments between turns of exactly 3 moves
+        solv.set_min_run(3);
+        solv.set_max_run(5).unwrap();
+        assert_eq!(solv.solve(false).map(|s| s.0), Ok(3));
+        solv.set_max_run(4).unwrap();
+        assert_eq!(solv.solve(false), Err(NoSolution::Constrained));
+    }
+
+    #[test]
+    fn cross_check() {
+        let maze = rand_maze(30, 75);
+        let new_solver = || {
+            let mut solv = Solver1::new_rc(maze.rows(), maze.cols()).unwrap();
+            for (r, c) in maze.passages() {
+                solv.set_passage(r, c);
+            }
+            solv
+        };
+        let mut solv = new_solver();
+        let unconstrained = reference_rank(&maze);
+        assert_eq!(solv.solve(false).ok().map(|s| s.0), unconstrained);
+        let mut last_rank = None;
+        for max_run in (1..6).rev() {
+            solv.set_max_run(max_run).unwrap();
+            match solv.solve(true) {
+                Ok((rank, path)) => {
+                    // Stricter constraint never makes the way simpler
+                    assert!(last_rank <= Some(rank));
+                    assert!(unconstrained.unwrap() <= rank);
+                    last_rank = Some(rank);
+                    assert_eq!(count_turns(&path), rank);
+                    assert!(runs(&path).iter().all(|run| *run <= max_run as usize));
+                },
+                Err(NoSolution::Constrained) => assert!(unconstrained.is_some()),
+                Err(NoSolution::NoWay) => assert!(unconstrained.is_none()),
+            }
+        }
+
+        // Minimal run compared against the unconstrained solve
+        let mut solv = new_solver();
+        solv.set_min_run(1);
+        assert_eq!(solv.solve(false).ok().map(|s| s.0), unconstrained);
+        let mut last_rank = None;
+        for min_run in 2..6 {
+            solv.set_min_run(min_run);
+            match solv.solve(true) {
+                Ok((rank, path)) => {
+                    assert!(last_rank <= Some(rank));
+                    assert!(unconstrained.unwrap() <= rank);
+                    last_rank = Some(rank);
+                    assert_eq!(count_turns(&path), rank);
+                    // Only the first and the last segments may be shorter
+                    let runs = runs(&path);
+                    assert!(runs.iter().skip(1).take(runs.len().saturating_sub(2)).all(|run| *run >= min_run as usize));
+                },
+                Err(NoSolution::Constrained) => assert!(unconstrained.is_some()),
+                Err(NoSolution::NoWay) => assert!(unconstrained.is_none()),
+            }
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::{HashMap, VecDeque};
+
+use super::*;
+use maze::Maze;
+
+/// The largest number of waypoints accepted with `WaypointOrder::Any`.
+/// Search keeps the set of waypoints passed so far in every state, so the number of states grows as 2^n.
+pub const MAX_ANY_WAYPOINTS: usize = 16;
+
+/// Order in which `solve_waypoints` passes thru the waypoints
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WaypointOrder {
+    /// In order of the `waypoints` argument
+    Given,
+    /// In any order (at most `MAX_ANY_WAYPOINTS` waypoints)
+    Any,
+}
+
+/// Finds the way from `start` to `end` with the fewest number of turns which passes thru all `waypoints`
+/// (in given or any order). Heading is carried across waypoints, so passing straight thru a waypoint is not a turn.
+/// Returns number of turns and the whole way (empty if not requested),
+/// or `None` also if start or end point is not a passage of the maze.
+/// Returns error if there are more than `MAX_ANY_WAYPOINTS` waypoints to pass in any order.
+pub fn solve_waypoints(maze: &Maze, start: NodeAdr, end: NodeAdr, waypoints: &[NodeAdr], order: WaypointOrder, with_path: bool)
+    -> Result<Option<(DimType, VecDeque<NodeAdr>)>, Error>
+{
+    if maze.check_endpoints(start, end).is_err() { return Ok(None); }
+    let (r_dim, c_dim) = (maze.rows(), maze.cols());
+    // Directions are indices of `Heading::ALL`
+    let neighbor = |(r, c): NodeAdr, direction: usize| {
+        let node = match Heading::ALL[direction] {
+            Heading::Up if r > 0 => (r - 1, c),
+            Heading::Right if c < c_dim - 1 => (r, c + 1),
+            Heading::Down if r < r_dim - 1 => (r + 1, c),
+            Heading::Left if c > 0 => (r, c - 1),
+            _ => return None,
+        };
+        if maze.is_passage(node.0, node.1) { Some(node) } else { None }
+    };
+    search(Heading::ALL.len(), neighbor, start, end, waypoints, order, with_path)
+}
+
+/// Finds the simplest way from start to end passing thru all waypoints, where `neighbor` returns
+/// the passage next to the node in given direction (one of `directions`).
+/// Returns number of turns and the way (empty if not requested),
+/// or error if there are more than `MAX_ANY_WAYPOINTS` waypoints to pass in any order.
+///
+/// It is equivalent to chaining solves of segments between consecutive waypoints with heading carried across them,
+/// but it is done in single 0-1 BFS on (node, direction, progress) states, where progress is the number
+/// of waypoints passed (or the set of them for any order). This way ties of headings at waypoints are not lost.
+/// Way may need to come back from waypoint, so moving back to the previous node is a turn here.
+pub(crate) fn search(directions: usize, neighbor: impl Fn(NodeAdr, usize) -> Option<NodeAdr>,
+    start: NodeAdr, end: NodeAdr, waypoints: &[NodeAdr], order: WaypointOrder, with_path: bool) -> Result<Option<(DimType, VecDeque<NodeAdr>)>, Error>
+{
+    if order == WaypointOrder::Any && waypoints.len() > MAX_ANY_WAYPOINTS {
+        return Err(Error::InvalidArgument(format!("Too many waypoints to pass in any order: {} (at most {})", waypoints.len(), MAX_ANY_WAYPOINTS)));
+    }
+    let advance = |node: NodeAdr, progress: u64| match order {
+        WaypointOrder::Given => {
+            let mut progress = progress as usize;
+            while progress < waypoints.len() && waypoints[progress] == node { progress += 1; }
+            progress as u64
+        },
+        WaypointOrder::Any => {
+            waypoints.iter().enumerate().filter(|(_, w)| **w == node).fold(progress, |progress, (i, _)| progress | 1 << i)
+        },
+    };
+    let complete = match order {
+        WaypointOrder::Given => waypoints.len() as u64,
+        WaypointOrder::Any => (0..waypoints.len()).fold(0, |mask, i| mask | 1 << i),
+    };
+
+    // (node, direction of the move into it, progress) => (rank, previous state)
+    type State = (NodeAdr, usize, u64);
+    let mut states = HashMap::<State, (DimType, Option<State>)>::new();
+    let mut todo = VecDeque::new();
+    // First move from start node in any direction is not a turn
+    for direction in 0..directions {
+        let state = (start, direction, advance(start, 0));
+        states.insert(state, (0, None));
+        todo.push_back((state, 0));
+    }
+    let mut end_state = None;
+    while let Some((state, rank)) = todo.pop_front() {
+        if states[&state].0 < rank { continue; }
+        let (node, direction, progress) = state;
+        if node == end && progress == complete {
+            end_state = Some(state);
+            break;
+        }
+        for next_direction in 0..directions {
+            let next_node = match neighbor(node, next_direction) {
+                Some(next_node) => next_node,
+                None => continue,
+            };
+            let next = (next_node, next_direction, advance(next_node, progress));
+            let next_rank = if next_direction == direction { rank } else { rank + 1 };
+            let better = match states.get(&next) {
+                Some((old_rank, _)) => next_rank < *old_rank,
+                None => true,
+            };
+            if better {
+                states.insert(next, (next_rank, Some(state)));
+                if next_rank == rank { todo.push_front((next, next_rank)); } else { todo.push_back((next, next_rank)); }
+            }
+        }
+    }
+
+    let end_state = match end_state {
+        Some(end_state) => end_state,
+        None => return Ok(None),
+    };
+    let mut path = VecDeque::new();
+    if with_path {
+        let mut state = Some(end_state);
+        while let Some(cur_state) = state {
+            path.push_front(cur_state.0);
+            state = states[&cur_state].1;
+        }
+    }
+    Ok(Some((states[&end_state].0, path)))
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use super::*;
+    use crate::tests::{rand_maze, reference_rank, count_turns};
+
+    #[test]
+    fn waypoints() {
+        // Corridor with dead end branch above it
+        let mut maze = Maze::new_rc(4, 6);
+        for c in 0..6 {
+            maze.set_passage(1, c);
+        }
+        maze.set_passage(0, 3);
+        maze.set_passage(2, 5);
+        let (start, end) = default_endpoints(4, 6);
+        let solve = |waypoints: &[NodeAdr], order, with_path| solve_waypoints(&maze, start, end, waypoints, order, with_path).unwrap();
+        // Straight pass thru the waypoint is not a turn
+        assert_eq!(solve(&[(1, 2), (1, 4)], WaypointOrder::Given, false), Some((1, VecDeque::new())));
+        assert_eq!(
+            solve(&[(0, 3)], WaypointOrder::Given, true),
+            Some((4, vec![(1, 0), (1, 1), (1, 2), (1, 3), (0, 3), (1, 3), (1, 4), (1, 5), (2, 5)].into_iter().collect()))
+        );
+        // Going back to the earlier waypoint
+        assert_eq!(solve(&[(1, 4), (1, 1)], WaypointOrder::Given, false).map(|s| s.0), Some(3));
+        assert_eq!(solve(&[(1, 4), (1, 1)], WaypointOrder::Any, false).map(|s| s.0), Some(1));
+        assert_eq!(solve(&[(1, 4), (0, 3)], WaypointOrder::Given, false).map(|s| s.0), Some(5));
+        assert_eq!(solve(&[(1, 4), (0, 0)], WaypointOrder::Any, false), None);
+        assert_eq!(solve(&[], WaypointOrder::Any, false).map(|s| s.0), reference_rank(&maze));
+        assert_eq!(solve_waypoints(&maze, start, (3, 3), &[], WaypointOrder::Given, false).unwrap(), None);
+        assert_eq!(solve_waypoints(&maze, start, (4, 0), &[], WaypointOrder::Given, false).unwrap(), None);
+
+        // Too many waypoints in any order is an error, while in given order they are fine
+        let waypoints = vec![(1, 3); MAX_ANY_WAYPOINTS + 1];
+        assert!(solve_waypoints(&maze, start, end, &waypoints[..MAX_ANY_WAYPOINTS], WaypointOrder::Any, false).unwrap().is_some());
+        assert!(matches!(solve_waypoints(&maze, start, end, &waypoints, WaypointOrder::Any, false), Err(Error::InvalidArgument(_))));
+        assert_eq!(solve(&waypoints, WaypointOrder::Given, false).map(|s| s.0), Some(1));
+    }
+
+    #[test]
+    fn cross_check() {
+        let mut rng = rand::thread_rng();
+        let maze = rand_maze(25, 75);
+        let (start, end) = default_endpoints(maze.rows(), maze.cols());
+        let waypoints: Vec<NodeAdr> = (0..3)
+            .map(|_| (rng.gen_range(0, maze.rows()), rng.gen_range(0, maze.cols())))
+            .filter(|node| maze.is_passage(node.0, node.1))
+            .collect();
+        let given = solve_waypoints(&maze, start, end, &waypoints, WaypointOrder::Given, true).unwrap();
+        let any = solve_waypoints(&maze, start, end, &waypoints, WaypointOrder::Any, true).unwrap();
+        assert_eq!(given.is_some(), any.is_some());
+        if let (Some((given_rank, given_path)), Some((any_rank, any_path))) = (given, any) {
+            assert!(any_rank <= given_rank);
+            assert!(reference_rank(&maze).unwrap() <= any_rank);
+            assert_eq!(count_turns(&given_path), given_rank);
+            assert_eq!(count_turns(&any_path), any_rank);
+            // Waypoints are passed in given order
+            let mut pos = 0;
+            for waypoint in waypoints.iter() {
+                pos += given_path.iter().skip(pos).position(|node| node == waypoint).unwrap();
+            }
+            assert!(waypoints.iter().all(|waypoint| any_path.contains(waypoint)));
+        }
+    }
+}
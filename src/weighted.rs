@@ -0,0 +1,190 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+use super::*;
+use maze::Maze;
+
+/// Costs of moves used by `solve_weighted`.
+/// Turns are counted at nodes where the heading changes; the first move from start is free of turn costs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CostModel {
+    /// Cost of every move to neighbor node
+    pub step: u64,
+    /// Cost of turn to the left (counterclockwise, e.g. from moving up to moving left)
+    pub left_turn: u64,
+    /// Cost of turn to the right (clockwise, e.g. from moving up to moving right)
+    pub right_turn: u64,
+    /// Cost of reversing back to the previous node
+    pub u_turn: u64,
+}
+
+/// Every turn costs 1 and moves are free - the objective of the original challenge
+impl Default for CostModel {
+    fn default() -> Self {
+        CostModel { step: 0, left_turn: 1, right_turn: 1, u_turn: 1 }
+    }
+}
+
+/// Numbers of moves of every kind along the way
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CostBreakdown {
+    pub steps: usize,
+    pub left_turns: usize,
+    pub right_turns: usize,
+    pub u_turns: usize,
+}
+
+/// The cheapest way found by `solve_weighted`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WeightedSolution {
+    /// Total cost of the way
+    pub cost: u64,
+    pub breakdown: CostBreakdown,
+    /// Nodes of the way - empty if not requested
+    pub path: VecDeque<NodeAdr>,
+}
+
+/// Kind of the turn between headings: 0 - none, 1 - right, 2 - U-turn, 3 - left
+#[inline]
+fn turn(from: Heading, to: Heading) -> usize {
+    (to as usize + 4 - from as usize) % 4
+}
+
+/// Finds the cheapest way from `start` to `end` according to `model`.
+/// Returns total cost, numbers of moves of every kind and the way (empty if not requested),
+/// or `None` also if start or end point is not a passage of the maze.
+///
+/// Left and right turns differ, so search runs on (node, heading) states like `dijkstra_dir`,
+/// with ranks being total costs. Costs are arbitrary, so plain Dijkstra with binary heap is used.
+pub fn solve_weighted(maze: &Maze, start: NodeAdr, end: NodeAdr, model: &CostModel, with_path: bool) -> Option<WeightedSolution> {
+    if maze.check_endpoints(start, end).is_err() { return None; }
+    let (r_dim, c_dim) = (maze.rows(), maze.cols());
+    let c_dim_u = c_dim as usize;
+    let idx = |(r, c): NodeAdr| r as usize * c_dim_u + c as usize;
+    let rc = |idx: usize| ((idx / c_dim_u) as DimType, (idx % c_dim_u) as DimType);
+    let turn_cost = [0, model.right_turn, model.u_turn, model.left_turn];
+    // State = node index * 4 + heading
+    let len = r_dim as usize * c_dim_u * 4;
+    let mut cost = vec![u64::MAX; len];
+    let mut prev = vec![usize::MAX; len];
+    let mut todo = BinaryHeap::new();
+    // Start node was not entered from anywhere, so first move in any direction is not a turn
+    for heading in 0..4 {
+        let state = idx(start) * 4 + heading;
+        cost[state] = 0;
+        prev[state] = state;
+        todo.push(Reverse((0, state)));
+    }
+    let mut end_state = None;
+    while let Some(Reverse((state_cost, state))) = todo.pop() {
+        if cost[state] < state_cost { continue; }
+        let (r, c) = rc(state / 4);
+        if (r, c) == end {
+            end_state = Some(state);
+            break;
+        }
+        let neighbors = [
+            (r > 0, (r.wrapping_sub(1), c), Heading::Up),
+            (c < c_dim - 1, (r, c.wrapping_add(1)), Heading::Right),
+            (r < r_dim - 1, (r.wrapping_add(1), c), Heading::Down),
+            (c > 0, (r, c.wrapping_sub(1)), Heading::Left),
+        ];
+        for (inside, node, heading) in neighbors.iter() {
+            if !inside || !maze.is_passage(node.0, node.1) { continue; }
+            let next = idx(*node) * 4 + *heading as usize;
+            let next_cost = state_cost + model.step + turn_cost[turn(Heading::ALL[state % 4], *heading)];
+            if next_cost < cost[next] {
+                cost[next] = next_cost;
+                prev[next] = state;
+                todo.push(Reverse((next_cost, next)));
+            }
+        }
+    }
+
+    let end_state = end_state?;
+    let mut breakdown = CostBreakdown::default();
+    let mut path = VecDeque::new();
+    let mut state = end_state;
+    if with_path {
+        path.push_front(rc(state / 4));
+    }
+    while prev[state] != state {
+        let prev_state = prev[state];
+        breakdown.steps += 1;
+        // Move from start node has no turn
+        if prev[prev_state] != prev_state {
+            match turn(Heading::ALL[prev_state % 4], Heading::ALL[state % 4]) {
+                1 => breakdown.right_turns += 1,
+                2 => breakdown.u_turns += 1,
+                3 => breakdown.left_turns += 1,
+                _ => {},
+            }
+        }
+        state = prev_state;
+        if with_path {
+            path.push_front(rc(state / 4));
+        }
+    }
+    Some(WeightedSolution { cost: cost[end_state], breakdown, path })
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{rand_maze, reference_rank, count_turns};
+
+    #[test]
+    fn turn() {
+        assert_eq!(super::turn(Heading::Up, Heading::Up), 0);
+        assert_eq!(super::turn(Heading::Up, Heading::Right), 1);
+        assert_eq!(super::turn(Heading::Left, Heading::Up), 1);
+        assert_eq!(super::turn(Heading::Right, Heading::Left), 2);
+        assert_eq!(super::turn(Heading::Up, Heading::Left), 3);
+    }
+
+    #[test]
+    fn solve_costs() {
+        // Junction at (3,1) with 2x2 block below-right of it, the end is above the junction
+        let mut maze = Maze::new_rc(6, 4);
+        for node in [(3, 0), (3, 1), (3, 2), (4, 1), (4, 2), (2, 1), (1, 1)].iter() {
+            maze.set_passage(node.0, node.1);
+        }
+        let (start, end) = ((3, 0), (1, 1));
+        let solution = solve_weighted(&maze, start, end, &CostModel::default(), true).unwrap();
+        assert_eq!(solution.cost, 1);
+        assert_eq!(solution.breakdown, CostBreakdown { steps:3, left_turns:1, right_turns:0, u_turns:0 });
+        assert_eq!(solution.path, vec![(3, 0), (3, 1), (2, 1), (1, 1)].into_iter().collect::<VecDeque<_>>());
+
+        // Three right turns around the block are cheaper than one left turn
+        let model = CostModel { step:1, left_turn:10, right_turn:1, u_turn:10 };
+        let solution = solve_weighted(&maze, start, end, &model, true).unwrap();
+        assert_eq!(solution.cost, 10);
+        assert_eq!(solution.breakdown, CostBreakdown { steps:7, left_turns:0, right_turns:3, u_turns:0 });
+        assert_eq!(solution.path, vec![(3, 0), (3, 1), (3, 2), (4, 2), (4, 1), (3, 1), (2, 1), (1, 1)].into_iter().collect::<VecDeque<_>>());
+        assert_eq!(solve_weighted(&maze, start, end, &model, false).unwrap().path, VecDeque::new());
+
+        let model = CostModel { step:1, left_turn:0, right_turn:0, u_turn:0 };
+        assert_eq!(solve_weighted(&maze, start, end, &model, false).unwrap().cost, 3);
+
+        assert_eq!(solve_weighted(&maze, start, (0, 0), &model, false), None);
+        assert_eq!(solve_weighted(&maze, start, (6, 0), &model, false), None);
+        assert_eq!(solve_weighted(&Maze::new_rc(3, 3), (1, 0), (1, 2), &model, false), None);
+    }
+
+    #[test]
+    fn cross_check() {
+        let maze = rand_maze(40, 75);
+        let (start, end) = default_endpoints(maze.rows(), maze.cols());
+        let solution = solve_weighted(&maze, start, end, &CostModel::default(), true);
+        assert_eq!(solution.as_ref().map(|s| s.cost), reference_rank(&maze).map(|rank| rank as u64));
+        if let Some(WeightedSolution { cost, breakdown, path }) = solution {
+            assert_eq!(count_turns(&path) as u64, cost);
+            assert_eq!((breakdown.left_turns + breakdown.right_turns + breakdown.u_turns) as u64, cost);
+            assert_eq!(breakdown.steps + 1, path.len());
+        }
+    }
+}